@@ -0,0 +1,44 @@
+use soroban_sdk::{Address, Env};
+
+use crate::storage::{self, BallotStatus};
+use crate::{token, validation, Error};
+
+// What addr could put in escrow, the tokens it already has there and its balance
+pub fn available(env: &Env, addr: &Address) -> Result<i128, Error> {
+    let tk = token::TokenClient::new(env, &storage::get_token(env)?);
+    Ok(storage::get_escrow(env, addr).saturating_add(tk.balance(addr)))
+}
+
+// Tops addr's escrow up to amount, tokens held by the ballot cannot be moved to a fresh address that votes again
+pub fn deposit(env: &Env, addr: &Address, amount: i128) -> Result<(), Error> {
+    let held = storage::get_escrow(env, addr);
+    if held >= amount {
+        return Ok(());
+    }
+
+    let tk = token::TokenClient::new(env, &storage::get_token(env)?);
+    if tk.balance(addr) < amount - held {
+        return Err(Error::VoterDoesNotHoldToken);
+    }
+
+    tk.transfer(addr, &env.current_contract_address(), &(amount - held));
+    storage::store_escrow(env, addr, amount);
+    Ok(())
+}
+
+// Tokens are released once nobody can vote anymore, a paused ballot may still be extended
+pub fn withdraw(env: &Env, addr: &Address) -> Result<i128, Error> {
+    let cfg = storage::get_config(env);
+    let closed = validation::current_point(env, &cfg.unit) > cfg.to && storage::get_paused_at(env).is_none();
+    if !closed && storage::get_ballot_status(env) == BallotStatus::Active {
+        return Err(Error::EscrowLocked);
+    }
+
+    let held = storage::get_escrow(env, addr);
+    if held > 0 {
+        storage::remove_escrow(env, addr);
+        token::TokenClient::new(env, &storage::get_token(env)?).transfer(&env.current_contract_address(), addr, &held);
+    }
+
+    Ok(held)
+}
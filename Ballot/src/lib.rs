@@ -1,17 +1,25 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracterror, Env, Symbol, Map, Address, Vec};
-
+use soroban_sdk::{contract, contractimpl, contracterror, contracttype, symbol_short, BytesN, Env, Symbol, Map, Address, Val, Vec};
+
+mod escrow;
+mod merkle;
+mod migration;
+mod proposals;
+mod provider;
+mod questions;
+mod receipt;
+mod results;
+mod schulze;
 mod storage;
+mod token;
 mod validation;
 
-mod token {
-    soroban_sdk::contractimport!(
-        file = "../BallotToken/target/wasm32-unknown-unknown/release/ballot_token.wasm"
-    );
-}
-
-use storage::VCounter;
+pub use questions::Answer;
+pub use receipt::Receipt;
+pub use results::{CandidateResult, Results};
+pub use schulze::RankedTally;
+use storage::{BallotStatus, Call, Delegation, Eligibility, Guardian, Method, Proposal, ProposalRules, ProposalStatus, Question, VRecord, WindowUnit};
 
 struct Voter<'a> {
     id: &'a Address
@@ -20,30 +28,20 @@ struct Voter<'a> {
 impl<'a> Voter<'a> {
     
     fn has_voted(&self, env: &Env) -> bool {
-        let vts: Vec<Address> = storage::get_votes(env);
-        vts.contains(self.id)
+        storage::get_voter_candidate(env, self.id).is_some()
     }
 
-    fn is_delegated(&self, env: &Env) -> bool {
-        let token = storage::get_token(&env);
-        let tk = token::Client::new(&env, &token);
-
-        if tk.blocking(&self.id) {
-            return true
-        }
+    fn has_participated(&self, env: &Env) -> bool {
+        self.has_voted(env) || storage::has_answered(env, self.id)
+    }
 
-        false
+    // A delegation whose weight was already cast stays binding after it expires, e.g. when unpausing extends the window
+    fn is_delegated(&self, env: &Env) -> bool {
+        storage::get_active_delegation_from(env, self.id).is_some() || storage::get_carrier(env, self.id).is_some()
     }
 
     fn has_delegated_vote(&self, env: &Env) -> bool {
-        let token = storage::get_token(&env);
-        let tk = token::Client::new(&env, &token);
-
-        if tk.allowance(&self.id) {
-            return true
-        }
-
-        false
+        storage::get_active_delegation_to(env, self.id).is_some()
     }
 }
 
@@ -51,12 +49,317 @@ fn check_dates(env: &Env) -> bool {
     let cfg = storage::get_config(env);
     let mut valid = true;
     if cfg.from > 0 && cfg.to > 0 {
-        valid = validation::is_valid_date(&env, &cfg.from, &cfg.to)
+        valid = validation::is_valid_date(env, &cfg.from, &cfg.to, &cfg.unit)
     }
 
     valid
 }
 
+// Balances are read at the end of the snapshot ledger, so it has to close before any vote lands or a holder
+// could vote, transfer and have the receiver vote within it. Timestamp ballots cannot know their opening ledger
+// in advance, the one before the first ledger they are used at stands in
+pub(crate) fn pin_snapshot(env: &Env) -> u32 {
+    storage::get_snapshot(env).unwrap_or_else(|| {
+        let ledger = env.ledger().sequence().saturating_sub(1);
+        storage::store_snapshot(env, ledger);
+        ledger
+    })
+}
+
+// Read only counterpart of the balance check votes make, timestamp ballots not yet used have no snapshot to read at
+fn snapshot_balance(env: &Env, addr: &Address) -> Result<i128, Error> {
+    let token = storage::get_token(env)?;
+    let balance = match storage::get_snapshot(env) {
+        Some(ledger) => token::BallotTokenClient::new(env, &token).balance_at(addr, &ledger),
+        None => token::TokenClient::new(env, &token).balance(addr)
+    };
+
+    Ok(balance)
+}
+
+// Returns the weight addr votes with when no proof is involved
+fn check_eligible(env: &Env, addr: &Address) -> Result<u32, Error> {
+    let (balance, min) = match storage::get_eligibility(env) {
+        Eligibility::Token => (token::BallotTokenClient::new(env, &storage::get_token(env)?).balance_at(addr, &pin_snapshot(env)), 1),
+        Eligibility::Balance(min) => (token::BallotTokenClient::new(env, &storage::get_token(env)?).balance_at(addr, &pin_snapshot(env)), min.max(1)),
+        Eligibility::Escrow(min) => (escrow::available(env, addr)?, min.max(1)),
+        Eligibility::Provider(provider) => return check_provider(env, &provider, addr),
+        Eligibility::Merkle(_) => return Err(Error::EligibilityProofRequired)
+    };
+
+    if balance < min {
+        return Err(Error::VoterDoesNotHoldToken);
+    }
+
+    Ok(1)
+}
+
+fn check_provider(env: &Env, provider: &Address, addr: &Address) -> Result<u32, Error> {
+    let client = provider::ProviderClient::new(env, provider);
+    if !client.is_eligible(addr) {
+        return Err(Error::VoterNotEligible);
+    }
+
+    Ok(client.weight(addr).clamp(1, u32::MAX as i128) as u32)
+}
+
+// Freezes the holder's token until the ballot ends so it cannot be transferred to a fresh address that votes again,
+// escrow ballots take the tokens instead. Balance ballots need no lock, their snapshot closes before the first vote
+// so a balance moved after voting never counts
+pub(crate) fn lock_token(env: &Env, addr: &Address) -> Result<(), Error> {
+    match storage::get_eligibility(env) {
+        Eligibility::Token => {
+            let cfg = storage::get_config(env);
+            let tk = token::BallotTokenClient::new(env, &storage::get_token(env)?);
+            tk.lock(&env.current_contract_address(), addr, &validation::expiration_ledger(env, &cfg));
+            Ok(())
+        },
+        Eligibility::Escrow(min) => escrow::deposit(env, addr, min.max(1)),
+        _ => Ok(())
+    }
+}
+
+fn check_proof(env: &Env, addr: &Address, weight: u32, proof: &Vec<BytesN<32>>) -> Result<(), Error> {
+    let tree = match storage::get_eligibility(env) {
+        Eligibility::Merkle(tree) => tree,
+        _ => return Err(Error::EligibilityProofNotExpected)
+    };
+
+    let leaf = merkle::leaf_hash(env, addr, weight);
+    if !merkle::verify(env, &tree.root, &leaf, proof) {
+        return Err(Error::InvalidEligibilityProof);
+    }
+
+    Ok(())
+}
+
+fn electorate(env: &Env) -> Result<u32, Error> {
+    let electorate = match storage::get_eligibility(env) {
+        Eligibility::Merkle(tree) => tree.voters,
+        Eligibility::Token => {
+            let token = storage::get_token(env)?;
+            let tk = token::BallotTokenClient::new(env, &token);
+            match storage::get_snapshot(env) {
+                Some(ledger) => tk.holders_at(&ledger),
+                None => tk.holders()
+            }
+        },
+        // Standard tokens and providers do not expose how many can vote
+        Eligibility::Balance(_) | Eligibility::Escrow(_) | Eligibility::Provider(_) => 0
+    };
+
+    Ok(electorate)
+}
+
+// Makes sure the token answers every query the eligibility mode will make, token ballots also need to be allowed to lock holders.
+// Merkle and provider ballots never read a token
+fn check_token(env: &Env, token: &Option<Address>, eligibility: &Eligibility) -> Result<(), Error> {
+    let ballot = env.current_contract_address();
+    let ledger = env.ledger().sequence();
+    let valid = match (eligibility, token) {
+        (Eligibility::Token, Some(token)) => {
+            let tk = token::BallotTokenClient::new(env, token);
+            matches!(tk.try_balance_at(&ballot, &ledger), Ok(Ok(_)))
+                && matches!(tk.try_holders_at(&ledger), Ok(Ok(_)))
+                && matches!(tk.try_is_locker(&ballot), Ok(Ok(true)))
+        },
+        // Live balances would let a holder vote, transfer and vote again from another address
+        (Eligibility::Balance(_), Some(token)) => {
+            matches!(token::BallotTokenClient::new(env, token).try_balance_at(&ballot, &ledger), Ok(Ok(_)))
+        },
+        // Tokens in escrow cannot vote twice, so any standard token will do
+        (Eligibility::Escrow(_), Some(token)) => {
+            matches!(token::TokenClient::new(env, token).try_balance(&ballot), Ok(Ok(_)))
+        },
+        (Eligibility::Token | Eligibility::Balance(_) | Eligibility::Escrow(_), None) => false,
+        (Eligibility::Provider(_) | Eligibility::Merkle(_), _) => true
+    };
+
+    if !valid {
+        return Err(Error::InvalidToken);
+    }
+
+    Ok(())
+}
+
+// Large provider or proof weights must fail the vote instead of wrapping the tallies
+pub(crate) fn add_weight(a: u32, b: u32) -> Result<u32, Error> {
+    a.checked_add(b).ok_or(Error::TallyOverflow)
+}
+
+fn check_version(env: &Env) -> Result<(), Error> {
+    if storage::get_version(env) < storage::SCHEMA_VERSION {
+        return Err(Error::MigrationRequired);
+    }
+
+    Ok(())
+}
+
+fn check_status(env: &Env) -> Result<(), Error> {
+    match storage::get_ballot_status(env) {
+        BallotStatus::Active => Ok(()),
+        BallotStatus::Vetoed => Err(Error::BallotVetoed),
+        BallotStatus::Canceled => Err(Error::BallotCanceled)
+    }
+}
+
+// Voting and governance calls are blocked while paused, guardian actions are not
+pub(crate) fn check_active(env: &Env) -> Result<(), Error> {
+    if storage::get_paused_at(env).is_some() {
+        return Err(Error::Paused);
+    }
+
+    check_status(env)
+}
+
+fn check_pauser(env: &Env, caller: &Address) -> Result<(), Error> {
+    caller.require_auth();
+    let is_guardian = storage::get_guardian(env).is_some_and(|g| g.address == *caller);
+    if *caller != storage::get_admin(env)? && !is_guardian {
+        return Err(Error::NotAuthorized);
+    }
+
+    Ok(())
+}
+
+// Once configured only the stored admin may reconfigure, and only until the window opens
+fn check_reconfigure(env: &Env, admin: &Address) -> Result<(), Error> {
+    let current = match storage::get_admin(env) {
+        Ok(a) => a,
+        Err(_) => return Ok(())
+    };
+
+    let cfg = storage::get_config(env);
+    if current != *admin || validation::current_point(env, &cfg.unit) >= cfg.from {
+        return Err(Error::AlreadyConfigured);
+    }
+
+    Ok(())
+}
+
+fn check_guardian(env: &Env) -> Result<Guardian, Error> {
+    let guardian = match storage::get_guardian(env) {
+        Some(g) => g,
+        None => return Err(Error::NoGuardian)
+    };
+
+    guardian.address.require_auth();
+    check_status(env)?;
+    Ok(guardian)
+}
+
+// A delegator is counted once however many races its delegate casts the delegated weight in
+fn carry(env: &Env, voter: &Address, delegator: Address) {
+    if storage::get_carrier(env, &delegator).is_none() {
+        storage::store_carried_delegators(env, voter, &Vec::from_array(env, [delegator]));
+        storage::update_carried_votes(env, storage::get_carried_votes(env) + 1);
+    }
+}
+
+fn cast_vote(env: &Env, voter: Address, candidate: Symbol, weight: u32) -> Result<Receipt, Error> {
+    check_version(env)?;
+    check_active(env)?;
+    if !check_dates(env) {
+        return Err(Error::BallotOutOfDate);
+    }
+
+    let v: Voter = Voter { id: &voter };
+
+    if v.is_delegated(env) {
+        return Err(Error::VoterHasHisVoteDelegated)
+    }
+
+    if v.has_voted(env) {
+        return Err(Error::VoterHasAlreadyVoted)
+    }
+    
+    if !storage::has_answered(env, &voter) {
+        storage::add_participant(env);
+    }
+
+    storage::store_party(env, &candidate);
+    storage::store_voter_candidate(env, &voter, &candidate);
+
+    let delegation = storage::get_active_delegation_to(env, &voter);
+    let d_votes = delegation.as_ref().map_or(0, |d| d.weight);
+
+    let votes = storage::get_candidate_votes_count(env, &candidate);
+    let count = add_weight(add_weight(weight, d_votes)?, votes)?;
+    let index = storage::store_voter(env, &voter);
+    let receipt = receipt::append(env, index, &voter, &candidate);
+
+    storage::update_candidate_count(env, &candidate, count);
+    results::record(env, votes, count);
+    if let Some(d) = delegation {
+        carry(env, &voter, d.delegator);
+        storage::update_candidate_delegated_count(env, &candidate, add_weight(d_votes, storage::get_candidate_delegated_count(env, &candidate))?);
+    }
+    storage::bump_instance(env);
+
+    Ok(receipt)
+}
+
+fn cast_answers(env: &Env, voter: Address, answers: Vec<Answer>, weight: u32) -> Result<bool, Error> {
+    check_version(env)?;
+    check_active(env)?;
+    if !check_dates(env) {
+        return Err(Error::BallotOutOfDate);
+    }
+
+    let v: Voter = Voter { id: &voter };
+
+    if v.is_delegated(env) {
+        return Err(Error::VoterHasHisVoteDelegated)
+    }
+
+    let first = !v.has_participated(env);
+    let delegation = storage::get_active_delegation_to(env, &voter);
+    let d_votes = delegation.as_ref().map_or(0, |d| d.weight);
+
+    questions::record(env, &voter, &answers, add_weight(weight, d_votes)?)?;
+    if let Some(d) = delegation {
+        carry(env, &voter, d.delegator);
+    }
+
+    if first {
+        storage::add_participant(env);
+    }
+    storage::bump_instance(env);
+
+    Ok(true)
+}
+
+fn check_delegation(env: &Env, o_voter: &Address, d_voter: &Address) -> Result<u32, Error> {
+    check_version(env)?;
+    check_active(env)?;
+    if !check_dates(env) {
+        return Err(Error::BallotOutOfDate);
+    }
+
+    let ov: Voter = Voter { id: o_voter };
+    let dv: Voter = Voter { id: d_voter };
+
+    // Both ov and dv have not been voted yet    
+    if ov.has_participated(env) {
+        return Err(Error::VoterOriginHasAlreadyVotedAndCannotDelegate)
+    }
+
+    if dv.has_participated(env) {
+        return Err(Error::VoterTargetHasAlreadyVotedAndCannotDelegate)
+    }
+
+    if ov.is_delegated(env) {
+        return Err(Error::VoterHasHisVoteDelegated)
+    }
+
+    if dv.has_delegated_vote(env) {
+        return Err(Error::VoterHasDelegatedVote)
+    }
+
+    let config = storage::get_config(env);
+    Ok(validation::expiration_ledger(env, &config))
+}
+
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -68,8 +371,92 @@ pub enum Error {
     VoterOriginHasAlreadyVotedAndCannotDelegate = 4,
     VoterTargetHasAlreadyVotedAndCannotDelegate = 5,
     BallotOutOfDate = 6,
-    VoterDoesNotHoldToken = 7
+    VoterDoesNotHoldToken = 7,
+    EligibilityProofRequired = 8,
+    EligibilityProofNotExpected = 9,
+    InvalidEligibilityProof = 10,
+    MigrationRequired = 11,
+    QuestionNotFound = 12,
+    QuestionAlreadyAnswered = 13,
+    InvalidOption = 14,
+    ProposalsDisabled = 15,
+    ProposalNotFound = 16,
+    ProposalNotPending = 17,
+    ProposalLimitReached = 18,
+    AlreadySponsored = 19,
+    BalanceBelowThreshold = 20,
+    ProposalNotPassed = 21,
+    ProposalAlreadyExecuted = 22,
+    ProposalNotQueued = 23,
+    TimelockNotExpired = 24,
+    NotAuthorized = 25,
+    ProposalCanceled = 26,
+    NoGuardian = 27,
+    VetoWindowClosed = 28,
+    BallotVetoed = 29,
+    BallotCanceled = 30,
+    Paused = 31,
+    VoterNotEligible = 32,
+    InvalidWindow = 33,
+    NotConfigured = 34,
+    InvalidToken = 35,
+    QuestionNotRanked = 36,
+    AlreadyConfigured = 37,
+    TallyOverflow = 38,
+    InvalidGuardian = 39,
+    EscrowLocked = 40
+
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Stats {
+    pub holders: u32,
+    pub voted: u32,
+    pub delegations: u32,
+    pub delegated_votes: u32,
+    pub abstentions: u32,
+    pub turnout_bps: u32
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum VoteChoice {
+    NotVoted,
+    Candidate(Symbol)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum DelegationTarget {
+    NotDelegated,
+    Delegate(Address)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct VoterStatus {
+    // None when eligibility can only be shown with a proof the ballot has not seen yet
+    pub eligible: Option<bool>,
+    pub voted: bool,
+    pub candidate: VoteChoice,
+    pub delegate: DelegationTarget,
+    pub delegators: Vec<Address>,
+    pub delegation_expiration: Option<u32>
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct VoteRecord {
+    pub candidate: Symbol,
+    pub carried: Vec<Address>
+}
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum DelegatedVote {
+    NotCast,
+    Cast(Address, Symbol)
 }
 
 #[contract]
@@ -78,116 +465,549 @@ pub struct Ballot;
 #[contractimpl]
 impl Ballot {
 
-    pub fn configure(env: Env, admin: Address, token: Address, ts_start: u64, ts_end: u64) -> Result<bool, Error> {
+    pub fn configure(env: Env, admin: Address, token: Option<Address>, start: u64, end: u64, unit: WindowUnit, eligibility: Eligibility) -> Result<bool, Error> {
         admin.require_auth();
-        storage::store_config(&env, ts_start, ts_end, token);
+        check_reconfigure(&env, &admin)?;
+        if start > end || end < validation::current_point(&env, &unit) {
+            return Err(Error::InvalidWindow);
+        }
+
+        // Ledger windows are compared against u32 sequence numbers
+        if unit == WindowUnit::Ledger && end > u32::MAX as u64 {
+            return Err(Error::InvalidWindow);
+        }
+
+        check_token(&env, &token, &eligibility)?;
+        storage::store_config(&env, admin, start, end, unit, token);
+        storage::store_eligibility(&env, &eligibility);
+        // Balances before the opening ledger decide the electorate, or before configuration if already open
+        if unit == WindowUnit::Ledger {
+            storage::store_snapshot(&env, (start as u32).max(env.ledger().sequence()).saturating_sub(1));
+        }
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    // Who may vote is part of the configuration, it cannot change once the window opens
+    pub fn set_eligibility(env: Env, eligibility: Eligibility) -> Result<bool, Error> {
+        let admin = storage::get_admin(&env)?;
+        admin.require_auth();
+        check_reconfigure(&env, &admin)?;
+        check_token(&env, &storage::get_token(&env).ok(), &eligibility)?;
+        storage::store_eligibility(&env, &eligibility);
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<bool, Error> {
+        storage::get_admin(&env)?.require_auth();
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(true)
+    }
+
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        storage::get_admin(&env)?.require_auth();
+        let version = migration::migrate(&env);
+        storage::bump_instance(&env);
+        Ok(version)
+    }
+
+    pub fn version(env: Env) -> u32 {
+        storage::get_version(&env)
+    }
+
+    pub fn set_retention(env: Env, retention: u32) -> Result<bool, Error> {
+        storage::get_admin(&env)?.require_auth();
+        storage::update_retention(&env, retention);
+        storage::bump_instance(&env);
         Ok(true)
     }
 
-    pub fn vote(env: Env, voter: Address, candidate: Symbol) -> Result<bool, Error> {
+    // Everything kept outside the instance grows with the ballot and is bumped page by page
+    pub fn bump(env: Env) {
+        storage::bump_instance(&env);
+    }
+
+    pub fn bump_questions(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let questions = storage::get_questions(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(questions.len());
+        for id in offset.min(end)..end {
+            for option in questions.get_unchecked(id).options.iter() {
+                storage::bump_persistent(&env, &VRecord::QTally(id, option));
+            }
+            storage::bump_persistent(&env, &VRecord::Pairwise(id));
+        }
+    }
+
+    pub fn bump_candidates(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let total = storage::get_candidates_count(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(total);
+        for i in offset.min(end)..end {
+            let party = storage::get_candidate(&env, i).unwrap();
+            storage::bump_persistent(&env, &VRecord::Party(i));
+            storage::bump_persistent(&env, &VRecord::Tally(party.clone()));
+            storage::bump_persistent(&env, &VRecord::DTally(party.clone()));
+            storage::bump_persistent(&env, &VRecord::PartyIndex(party));
+        }
+    }
+
+    pub fn bump_proposals(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let total = storage::get_proposals_count(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(total);
+        for id in offset.min(end)..end {
+            let proposal = storage::get_proposal(&env, id).unwrap();
+            storage::bump_persistent(&env, &VRecord::Proposal(id));
+            storage::bump_persistent(&env, &VRecord::Proposals(proposal.proposer));
+        }
+    }
+
+    pub fn bump_voters(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let total = storage::get_voters_count(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(total);
+        for i in offset.min(end)..end {
+            let voter = storage::get_voter(&env, i).unwrap();
+            storage::bump_persistent(&env, &VRecord::Voter(i));
+            storage::bump_persistent(&env, &VRecord::Leaf(i));
+            storage::bump_persistent(&env, &VRecord::Vote(voter.clone()));
+            storage::bump_persistent(&env, &VRecord::Escrow(voter.clone()));
+            for delegator in storage::get_carried_delegators(&env, &voter).iter() {
+                storage::bump_persistent(&env, &VRecord::CarriedBy(delegator));
+            }
+            storage::bump_persistent(&env, &VRecord::Carried(voter));
+        }
+    }
+
+    pub fn bump_delegations(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let total = storage::get_delegations_count(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(total);
+        for i in offset.min(end)..end {
+            let d = storage::get_delegation(&env, i).unwrap();
+            storage::bump_persistent(&env, &VRecord::Delegation(i));
+            storage::bump_persistent(&env, &VRecord::Escrow(d.delegator.clone()));
+            storage::bump_persistent(&env, &VRecord::DelegatedFrom(d.delegator));
+            storage::bump_persistent(&env, &VRecord::DelegatedTo(d.delegate));
+        }
+    }
+
+    pub fn vote(env: Env, voter: Address, candidate: Symbol) -> Result<Receipt, Error> {
         voter.require_auth();
+        let weight = check_eligible(&env, &voter)?;
+        let receipt = cast_vote(&env, voter.clone(), candidate, weight)?;
+        lock_token(&env, &voter)?;
+        Ok(receipt)
+    }
+
+    pub fn vote_with_proof(env: Env, voter: Address, candidate: Symbol, weight: u32, proof: Vec<BytesN<32>>) -> Result<Receipt, Error> {
+        voter.require_auth();
+        check_proof(&env, &voter, weight, &proof)?;
+        cast_vote(&env, voter, candidate, weight)
+    }
+
+    pub fn delegate(env: Env, o_voter: Address, d_voter: Address) -> Result<bool, Error> {
+
+        o_voter.require_auth();
+        let weight = check_eligible(&env, &o_voter)?;
+        check_eligible(&env, &d_voter)?;
+
+        let expiration_ledger = check_delegation(&env, &o_voter, &d_voter)?;
+        lock_token(&env, &o_voter)?;
+        storage::store_delegation(&env, Delegation { delegator: o_voter, delegate: d_voter, weight, expiration: expiration_ledger });
+        storage::bump_instance(&env);
+
+        Ok(true)
+
+    }
+
+    pub fn delegate_with_proof(env: Env, o_voter: Address, o_weight: u32, o_proof: Vec<BytesN<32>>, d_voter: Address, d_weight: u32, d_proof: Vec<BytesN<32>>) -> Result<bool, Error> {
 
-        let token = storage::get_token(&env);
-        let tk = token::Client::new(&env, &token);
+        o_voter.require_auth();
+        check_proof(&env, &o_voter, o_weight, &o_proof)?;
+        check_proof(&env, &d_voter, d_weight, &d_proof)?;
+
+        let expiration_ledger = check_delegation(&env, &o_voter, &d_voter)?;
+        storage::store_delegation(&env, Delegation { delegator: o_voter, delegate: d_voter, weight: o_weight, expiration: expiration_ledger });
+        storage::bump_instance(&env);
+
+        Ok(true)
+    }
 
-        if tk.balance(&voter) < 1 {
-            return Err(Error::VoterDoesNotHoldToken);
+    pub fn add_question(env: Env, title: Symbol, options: Vec<Symbol>, method: Method) -> Result<u32, Error> {
+        storage::get_admin(&env)?.require_auth();
+        if options.is_empty() {
+            return Err(Error::InvalidOption);
         }
-        
-        if !check_dates(&env) {
-            return Err(Error::BallotOutOfDate);
+
+        let id = storage::store_question(&env, Question { title, options, method });
+        storage::bump_instance(&env);
+        Ok(id)
+    }
+
+    pub fn questions(env: Env) -> Vec<Question> {
+        storage::get_questions(&env)
+    }
+
+    pub fn vote_questions(env: Env, voter: Address, answers: Vec<Answer>) -> Result<bool, Error> {
+        voter.require_auth();
+        let weight = check_eligible(&env, &voter)?;
+        cast_answers(&env, voter.clone(), answers, weight)?;
+        lock_token(&env, &voter)?;
+        Ok(true)
+    }
+
+    pub fn vote_questions_with_proof(env: Env, voter: Address, answers: Vec<Answer>, weight: u32, proof: Vec<BytesN<32>>) -> Result<bool, Error> {
+        voter.require_auth();
+        check_proof(&env, &voter, weight, &proof)?;
+        cast_answers(&env, voter, answers, weight)
+    }
+
+    pub fn question_count(env: Env, question: u32) -> Result<Map<Symbol, u32>, Error> {
+        let q = match storage::get_questions(&env).get(question) {
+            Some(q) => q,
+            None => return Err(Error::QuestionNotFound)
+        };
+
+        let mut count_map: Map<Symbol, u32> = Map::new(&env);
+        for option in q.options.iter() {
+            let option_count = storage::get_question_votes_count(&env, question, &option);
+            count_map.set(option, option_count);
         }
 
-        let v: Voter = Voter { id: &voter };
+        Ok(count_map)
+    }
 
-        if v.is_delegated(&env) {
-            return Err(Error::VoterHasHisVoteDelegated)
+    pub fn ranked_tally(env: Env, question: u32) -> Result<RankedTally, Error> {
+        let q = match storage::get_questions(&env).get(question) {
+            Some(q) => q,
+            None => return Err(Error::QuestionNotFound)
+        };
+
+        if q.method != Method::Ranked {
+            return Err(Error::QuestionNotRanked);
         }
 
-        if v.has_voted(&env) {
-            return Err(Error::VoterHasAlreadyVoted)
+        Ok(schulze::tally(&env, question, &q.options))
+    }
+
+    // The guardian checks the admin, so it is fixed once voting starts and can never be the admin itself
+    pub fn set_guardian(env: Env, guardian: Guardian) -> Result<bool, Error> {
+        let admin = storage::get_admin(&env)?;
+        admin.require_auth();
+        let cfg = storage::get_config(&env);
+        if validation::current_point(&env, &cfg.unit) >= cfg.from {
+            return Err(Error::AlreadyConfigured);
         }
-        
-        storage::store_party(&env, &candidate);
 
-        let mut votes: Vec<Address> = storage::get_votes(&env);
-        let candidate_key: VCounter = VCounter::Counter(candidate);
-        let mut d_votes = 0;
-        if v.has_delegated_vote(&env) {
-            d_votes = 1;
+        if guardian.address == admin {
+            return Err(Error::InvalidGuardian);
         }
 
-        let count = 1 + d_votes + storage::get_candidate_votes_count(&env, &candidate_key);
-        votes.push_back(voter);
- 
-        storage::update_candidate_count(&env, candidate_key, count);
-        storage::update_votes(&env, votes);
+        storage::store_guardian(&env, &guardian);
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn veto(env: Env, reason: BytesN<32>) -> Result<bool, Error> {
+        let guardian = check_guardian(&env)?;
+        let cfg = storage::get_config(&env);
+        let current = validation::current_point(&env, &cfg.unit);
+        if current <= cfg.to || current > cfg.to.saturating_add(guardian.veto_window) {
+            return Err(Error::VetoWindowClosed);
+        }
 
+        storage::update_ballot_status(&env, BallotStatus::Vetoed, &reason);
+        storage::bump_instance(&env);
+        env.events().publish((symbol_short!("ballot"), symbol_short!("vetoed")), reason);
         Ok(true)
     }
 
-    pub fn delegate(env: Env, o_voter: Address, d_voter: Address) -> Result<bool, Error> {
+    pub fn cancel_ballot(env: Env, reason: BytesN<32>) -> Result<bool, Error> {
+        let guardian = check_guardian(&env)?;
+        let cfg = storage::get_config(&env);
+        if validation::current_point(&env, &cfg.unit) > cfg.to.saturating_add(guardian.veto_window) {
+            return Err(Error::VetoWindowClosed);
+        }
 
-        o_voter.require_auth();
-        let token = storage::get_token(&env);
-        let tk = token::Client::new(&env, &token);
+        storage::update_ballot_status(&env, BallotStatus::Canceled, &reason);
+        storage::bump_instance(&env);
+        env.events().publish((symbol_short!("ballot"), symbol_short!("canceled")), reason);
+        Ok(true)
+    }
 
-        if tk.balance(&o_voter) < 1 {
-            return Err(Error::VoterDoesNotHoldToken);
+    pub fn pause(env: Env, caller: Address) -> Result<bool, Error> {
+        check_pauser(&env, &caller)?;
+        if storage::get_paused_at(&env).is_some() {
+            return Err(Error::Paused);
         }
 
-        if tk.balance(&d_voter) < 1 {
-            return Err(Error::VoterDoesNotHoldToken);
+        let cfg = storage::get_config(&env);
+        storage::update_paused_at(&env, Some(validation::current_point(&env, &cfg.unit)));
+        storage::bump_instance(&env);
+        env.events().publish((symbol_short!("ballot"), symbol_short!("paused")), caller);
+        Ok(true)
+    }
+
+    pub fn unpause(env: Env, caller: Address, extend: bool) -> Result<bool, Error> {
+        check_pauser(&env, &caller)?;
+        let paused_at = match storage::get_paused_at(&env) {
+            Some(p) => p,
+            None => return Ok(false)
+        };
+
+        let mut cfg = storage::get_config(&env);
+        if extend && cfg.to > 0 {
+            cfg.to += validation::current_point(&env, &cfg.unit).saturating_sub(paused_at);
+            storage::update_config(&env, &cfg);
+        }
+
+        storage::update_paused_at(&env, None);
+        storage::bump_instance(&env);
+        env.events().publish((symbol_short!("ballot"), symbol_short!("unpaused")), (caller, cfg.to));
+        Ok(true)
+    }
+
+    pub fn paused(env: Env) -> bool {
+        storage::get_paused_at(&env).is_some()
+    }
+
+    pub fn ballot_status(env: Env) -> BallotStatus {
+        storage::get_ballot_status(&env)
+    }
+
+    pub fn status_reason(env: Env) -> Option<BytesN<32>> {
+        storage::get_status_reason(&env)
+    }
+
+    pub fn set_proposal_rules(env: Env, rules: ProposalRules) -> Result<bool, Error> {
+        storage::get_admin(&env)?.require_auth();
+        storage::store_proposal_rules(&env, &rules);
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn propose(env: Env, proposer: Address, title: Symbol, description: BytesN<32>, options: Vec<Symbol>, actions: Vec<Call>) -> Result<u32, Error> {
+        proposer.require_auth();
+        if let Eligibility::Merkle(_) = storage::get_eligibility(&env) {
+            return Err(Error::EligibilityProofRequired);
         }
 
+        check_active(&env)?;
         if !check_dates(&env) {
             return Err(Error::BallotOutOfDate);
         }
 
-        let ov: Voter = Voter { id: &o_voter };
-        let dv: Voter = Voter { id: &d_voter };
-    
-        
-        // Both ov and dv have not been voted yet    
-        if ov.has_voted(&env) {
-            return Err(Error::VoterOriginHasAlreadyVotedAndCannotDelegate)
+        let id = proposals::propose(&env, proposer, title, description, options, actions)?;
+        storage::bump_instance(&env);
+        Ok(id)
+    }
+
+    pub fn sponsor(env: Env, sponsor: Address, id: u32) -> Result<ProposalStatus, Error> {
+        sponsor.require_auth();
+        if let Eligibility::Merkle(_) = storage::get_eligibility(&env) {
+            return Err(Error::EligibilityProofRequired);
         }
 
-        if dv.has_voted(&env) {
-            return Err(Error::VoterTargetHasAlreadyVotedAndCannotDelegate)
+        check_active(&env)?;
+        if !check_dates(&env) {
+            return Err(Error::BallotOutOfDate);
         }
 
+        let status = proposals::sponsor(&env, sponsor, id)?;
+        storage::bump_instance(&env);
+        Ok(status)
+    }
 
-        if ov.is_delegated(&env) {
-            return Err(Error::VoterHasHisVoteDelegated)
+    // Members decide knowing how long they will have before execution, so the delay is fixed once voting starts
+    pub fn set_timelock(env: Env, delay: u64) -> Result<bool, Error> {
+        storage::get_admin(&env)?.require_auth();
+        let cfg = storage::get_config(&env);
+        if validation::current_point(&env, &cfg.unit) >= cfg.from {
+            return Err(Error::AlreadyConfigured);
         }
 
-        if dv.has_delegated_vote(&env) {
-            return Err(Error::VoterHasDelegatedVote)
-        }
+        storage::update_timelock_delay(&env, delay);
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn queue(env: Env, id: u32) -> Result<u64, Error> {
+        let eta = proposals::queue(&env, id)?;
+        storage::bump_instance(&env);
+        Ok(eta)
+    }
 
-        let config = storage::get_config(&env);
-        let expiration_ledger = (((config.to - config.from) / 5) + 60) as u32; // 5 seconds for every ledger. Add 5 extra minutes
+    pub fn cancel(env: Env, caller: Address, id: u32) -> Result<bool, Error> {
+        caller.require_auth();
+        let canceled = proposals::cancel(&env, &caller, id)?;
+        storage::bump_instance(&env);
+        Ok(canceled)
+    }
 
-        tk.approve(&o_voter, &d_voter, &expiration_ledger);
+    pub fn execute(env: Env, id: u32) -> Result<Vec<Val>, Error> {
+        let results = proposals::execute(&env, id)?;
+        storage::bump_instance(&env);
+        Ok(results)
+    }
 
-        Ok(true)
+    pub fn proposal(env: Env, id: u32) -> Result<Proposal, Error> {
+        proposals::get(&env, id)
+    }
+
+    pub fn withdraw(env: Env, addr: Address) -> Result<i128, Error> {
+        let amount = escrow::withdraw(&env, &addr)?;
+        storage::bump_instance(&env);
+        Ok(amount)
+    }
 
+    pub fn escrowed(env: Env, addr: Address) -> i128 {
+        storage::get_escrow(&env, &addr)
     }
 
-    pub fn count(env: Env,  admin: Address) -> Map<Symbol, u32> {
+    pub fn count(env: Env,  admin: Address, offset: u32, limit: u32) -> Map<Symbol, u32> {
         
         admin.require_auth();
-        let pts = storage::get_candidates(&env);
+        let pts = storage::get_candidates_page(&env, offset, limit);
         let mut count_map: Map<Symbol, u32>= Map::new(&env);
         for party in pts.iter() {
-            let candidate_key = VCounter::Counter(party.clone());
-            let candidate_count: u32 = storage::get_candidate_votes_count(&env, &candidate_key);
+            let candidate_count: u32 = storage::get_candidate_votes_count(&env, &party);
             count_map.set(party, candidate_count);
         }
 
         count_map
     }
+
+    pub fn results(env: Env, admin: Address, offset: u32, limit: u32) -> Result<Results, Error> {
+        admin.require_auth();
+        if admin != storage::get_admin(&env)? {
+            return Err(Error::NotAuthorized);
+        }
+
+        Ok(results::tally(&env, electorate(&env)?, offset, limit))
+    }
+
+    pub fn list_voters(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        storage::get_voters(&env, offset, limit)
+    }
+
+    pub fn list_candidates(env: Env, offset: u32, limit: u32) -> Vec<Symbol> {
+        storage::get_candidates_page(&env, offset, limit)
+    }
+
+    pub fn list_delegations(env: Env, offset: u32, limit: u32) -> Vec<Delegation> {
+        storage::get_delegations(&env, offset, limit)
+    }
+
+    pub fn voter_status(env: Env, voter: Address) -> Result<VoterStatus, Error> {
+        // Delegations whose weight was cast are reported after they expire so the status stays auditable
+        let mut delegate = DelegationTarget::NotDelegated;
+        let mut delegation_expiration = None;
+        let carrier = storage::get_carrier(&env, &voter);
+        if let Some(d) = storage::get_delegation_from(&env, &voter) {
+            if d.expiration >= env.ledger().sequence() || carrier == Some(d.delegate.clone()) {
+                delegate = DelegationTarget::Delegate(d.delegate);
+                delegation_expiration = Some(d.expiration);
+            }
+        }
+
+        let mut delegators = storage::get_carried_delegators(&env, &voter);
+        if delegators.is_empty() {
+            if let Some(d) = storage::get_active_delegation_to(&env, &voter) {
+                delegators.push_back(d.delegator);
+            }
+        }
+
+        let candidate = match storage::get_voter_candidate(&env, &voter) {
+            Some(c) => VoteChoice::Candidate(c),
+            None => VoteChoice::NotVoted
+        };
+
+        let voted = candidate != VoteChoice::NotVoted;
+        let eligible = match storage::get_eligibility(&env) {
+            // The root does not tell who is in the tree, only a voter that already acted proved it
+            Eligibility::Merkle(_) => match voted || delegate != DelegationTarget::NotDelegated || !delegators.is_empty() {
+                true => Some(true),
+                false => None
+            },
+            Eligibility::Token => Some(snapshot_balance(&env, &voter)? > 0),
+            Eligibility::Balance(min) => Some(snapshot_balance(&env, &voter)? >= min.max(1)),
+            Eligibility::Escrow(min) => Some(escrow::available(&env, &voter)? >= min.max(1)),
+            Eligibility::Provider(provider) => Some(provider::ProviderClient::new(&env, &provider).is_eligible(&voter))
+        };
+
+        Ok(VoterStatus {
+            eligible,
+            voted,
+            candidate,
+            delegate,
+            delegators,
+            delegation_expiration
+        })
+    }
+
+    pub fn vote_record(env: Env, voter: Address) -> Option<VoteRecord> {
+        let candidate = storage::get_voter_candidate(&env, &voter)?;
+        Some(VoteRecord {
+            candidate,
+            carried: storage::get_carried_delegators(&env, &voter)
+        })
+    }
+
+    // Where the delegator's weight ended up, the delegate and the candidate it voted for
+    pub fn delegated_vote(env: Env, delegator: Address) -> DelegatedVote {
+        let delegate = match storage::get_carrier(&env, &delegator) {
+            Some(d) => d,
+            None => return DelegatedVote::NotCast
+        };
+
+        match storage::get_voter_candidate(&env, &delegate) {
+            Some(candidate) => DelegatedVote::Cast(delegate, candidate),
+            None => DelegatedVote::NotCast
+        }
+    }
+
+    pub fn votes_root(env: Env) -> BytesN<32> {
+        storage::get_root(&env)
+    }
+
+    pub fn verify_receipt(env: Env, receipt: Receipt, proof: Vec<BytesN<32>>) -> bool {
+        receipt::verify_stored(&env, &receipt, &proof)
+    }
+
+    pub fn snapshot(env: Env) -> Option<u32> {
+        storage::get_snapshot(&env)
+    }
+
+    pub fn stats(env: Env) -> Result<Stats, Error> {
+        let holders = electorate(&env)?;
+        let voted = storage::get_participants_count(&env);
+        let delegated_votes = storage::get_carried_votes(&env);
+        // Once the window closes only delegations whose weight was cast still bind anyone
+        let cfg = storage::get_config(&env);
+        let delegations = match validation::current_point(&env, &cfg.unit) > cfg.to {
+            true => delegated_votes,
+            false => storage::get_active_delegations_count(&env)
+        };
+
+        // Both voters and carried delegators are counted by head, whatever weight they voted with
+        let participants = voted + delegated_votes;
+        let turnout_bps = match holders {
+            0 => 0,
+            _ => (participants as u64 * 10000 / holders as u64).min(10000) as u32
+        };
+
+        Ok(Stats {
+            holders,
+            voted,
+            delegations,
+            delegated_votes,
+            abstentions: holders.saturating_sub(participants),
+            turnout_bps
+        })
+    }
 }
 
 mod test;
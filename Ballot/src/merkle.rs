@@ -0,0 +1,24 @@
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, Vec};
+
+pub fn leaf_hash(env: &Env, voter: &Address, weight: u32) -> BytesN<32> {
+    let mut data = voter.clone().to_xdr(env);
+    data.extend_from_array(&weight.to_be_bytes());
+    env.crypto().sha256(&data)
+}
+
+// Pairs are hashed in sorted order so proofs do not need to carry the node positions
+pub fn hash_pair(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (first, second) = if a < b { (a, b) } else { (b, a) };
+    let mut data = Bytes::from_array(env, &first.to_array());
+    data.extend_from_array(&second.to_array());
+    env.crypto().sha256(&data)
+}
+
+pub fn verify(env: &Env, root: &BytesN<32>, leaf: &BytesN<32>, proof: &Vec<BytesN<32>>) -> bool {
+    let mut node = leaf.clone();
+    for sibling in proof.iter() {
+        node = hash_pair(env, &node, &sibling);
+    }
+
+    node == *root
+}
@@ -0,0 +1,9 @@
+use soroban_sdk::Env;
+
+use crate::storage::{self, SCHEMA_VERSION};
+
+// Each step moves the storage one version forward, the first upgradeable layout has none yet
+pub fn migrate(env: &Env) -> u32 {
+    storage::update_version(env, SCHEMA_VERSION);
+    SCHEMA_VERSION
+}
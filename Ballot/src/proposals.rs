@@ -0,0 +1,200 @@
+use soroban_sdk::{symbol_short, Address, BytesN, Env, Symbol, Val, Vec};
+
+use crate::storage::{self, Call, Eligibility, Method, Proposal, ProposalRules, ProposalStatus, Question};
+use crate::{check_active, escrow, lock_token, pin_snapshot, provider, token, validation, Error};
+
+fn get_rules(env: &Env) -> Result<ProposalRules, Error> {
+    match storage::get_proposal_rules(env) {
+        Some(rules) => Ok(rules),
+        None => Err(Error::ProposalsDisabled)
+    }
+}
+
+// Proposers and sponsors are weighed like voters, at the snapshot and locked until the ballot ends.
+// Escrow ballots hold the whole threshold so it cannot back a second proposal from another address
+fn check_balance(env: &Env, addr: &Address, rules: &ProposalRules) -> Result<(), Error> {
+    let eligibility = storage::get_eligibility(env);
+    let balance = match &eligibility {
+        Eligibility::Token | Eligibility::Balance(_) => token::BallotTokenClient::new(env, &storage::get_token(env)?).balance_at(addr, &pin_snapshot(env)),
+        Eligibility::Escrow(_) => escrow::available(env, addr)?,
+        Eligibility::Provider(provider) => {
+            let client = provider::ProviderClient::new(env, provider);
+            if !client.is_eligible(addr) {
+                return Err(Error::VoterNotEligible);
+            }
+
+            client.weight(addr)
+        },
+        Eligibility::Merkle(_) => return Err(Error::EligibilityProofRequired)
+    };
+
+    if balance < rules.min_balance.max(1) as i128 {
+        return Err(Error::BalanceBelowThreshold);
+    }
+
+    match eligibility {
+        Eligibility::Escrow(min) => escrow::deposit(env, addr, min.max(rules.min_balance.max(1) as i128)),
+        _ => lock_token(env, addr)
+    }
+}
+
+// Once enough sponsors back the proposal its options are put to the vote as a new question
+fn open(env: &Env, proposal: &mut Proposal) {
+    let question = Question {
+        title: proposal.title.clone(),
+        options: proposal.options.clone(),
+        method: Method::Plurality
+    };
+
+    proposal.question = Some(storage::store_question(env, question));
+    proposal.status = ProposalStatus::Open;
+}
+
+pub fn get(env: &Env, id: u32) -> Result<Proposal, Error> {
+    match storage::get_proposal(env, id) {
+        Some(proposal) => Ok(proposal),
+        None => Err(Error::ProposalNotFound)
+    }
+}
+
+pub fn propose(env: &Env, proposer: Address, title: Symbol, description: BytesN<32>, options: Vec<Symbol>, actions: Vec<Call>) -> Result<u32, Error> {
+    let rules = get_rules(env)?;
+    check_balance(env, &proposer, &rules)?;
+
+    if storage::get_proposer_count(env, &proposer) >= rules.max_per_address {
+        return Err(Error::ProposalLimitReached);
+    }
+
+    if options.is_empty() {
+        return Err(Error::InvalidOption);
+    }
+
+    let mut proposal = Proposal {
+        proposer,
+        title,
+        description,
+        options,
+        sponsors: Vec::new(env),
+        status: ProposalStatus::Pending,
+        question: None,
+        actions,
+        eta: 0
+    };
+
+    if rules.sponsors == 0 {
+        open(env, &mut proposal);
+    }
+
+    Ok(storage::store_proposal(env, &proposal))
+}
+
+pub fn sponsor(env: &Env, sponsor: Address, id: u32) -> Result<ProposalStatus, Error> {
+    let rules = get_rules(env)?;
+    let mut proposal = get(env, id)?;
+    check_balance(env, &sponsor, &rules)?;
+
+    if proposal.status != ProposalStatus::Pending {
+        return Err(Error::ProposalNotPending);
+    }
+
+    if proposal.proposer == sponsor || proposal.sponsors.contains(&sponsor) {
+        return Err(Error::AlreadySponsored);
+    }
+
+    proposal.sponsors.push_back(sponsor);
+    if proposal.sponsors.len() >= rules.sponsors {
+        open(env, &mut proposal);
+    }
+
+    storage::update_proposal(env, id, &proposal);
+    Ok(proposal.status)
+}
+
+// A proposal passes when the ballot is over and its first option beats every other one
+pub fn passed(env: &Env, proposal: &Proposal) -> bool {
+    let question = match (proposal.status, proposal.question) {
+        (ProposalStatus::Open, Some(q)) => q,
+        _ => return false
+    };
+
+    let cfg = storage::get_config(env);
+    if validation::current_point(env, &cfg.unit) <= cfg.to {
+        return false;
+    }
+
+    let first = storage::get_question_votes_count(env, question, &proposal.options.get(0).unwrap());
+    if first == 0 {
+        return false;
+    }
+
+    proposal.options
+        .iter()
+        .skip(1)
+        .all(|option| storage::get_question_votes_count(env, question, &option) < first)
+}
+
+// With a guardian, execution also waits until the veto window is over
+pub fn queue(env: &Env, id: u32) -> Result<u64, Error> {
+    check_active(env)?;
+    let mut proposal = get(env, id)?;
+    if !passed(env, &proposal) {
+        return Err(Error::ProposalNotPassed);
+    }
+
+    let cfg = storage::get_config(env);
+    let veto_window = storage::get_guardian(env).map(|g| g.veto_window).unwrap_or(0);
+    proposal.eta = validation::current_point(env, &cfg.unit)
+        .saturating_add(storage::get_timelock_delay(env))
+        .max(cfg.to.saturating_add(veto_window).saturating_add(1))
+    ;
+    proposal.status = ProposalStatus::Queued;
+    storage::update_proposal(env, id, &proposal);
+
+    env.events().publish((symbol_short!("proposal"), symbol_short!("queued")), (id, proposal.eta));
+    Ok(proposal.eta)
+}
+
+pub fn execute(env: &Env, id: u32) -> Result<Vec<Val>, Error> {
+    check_active(env)?;
+    let mut proposal = get(env, id)?;
+    match proposal.status {
+        ProposalStatus::Queued => (),
+        ProposalStatus::Executed => return Err(Error::ProposalAlreadyExecuted),
+        _ => return Err(Error::ProposalNotQueued)
+    }
+
+    let cfg = storage::get_config(env);
+    if validation::current_point(env, &cfg.unit) < proposal.eta {
+        return Err(Error::TimelockNotExpired);
+    }
+
+    proposal.status = ProposalStatus::Executed;
+    storage::update_proposal(env, id, &proposal);
+
+    let mut results: Vec<Val> = Vec::new(env);
+    for call in proposal.actions.iter() {
+        results.push_back(env.invoke_contract::<Val>(&call.contract, &call.function, call.args));
+    }
+
+    env.events().publish((symbol_short!("proposal"), symbol_short!("executed")), id);
+    Ok(results)
+}
+
+pub fn cancel(env: &Env, caller: &Address, id: u32) -> Result<bool, Error> {
+    let mut proposal = get(env, id)?;
+    if *caller != proposal.proposer && *caller != storage::get_admin(env)? {
+        return Err(Error::NotAuthorized);
+    }
+
+    match proposal.status {
+        ProposalStatus::Executed => return Err(Error::ProposalAlreadyExecuted),
+        ProposalStatus::Canceled => return Err(Error::ProposalCanceled),
+        _ => ()
+    }
+
+    proposal.status = ProposalStatus::Canceled;
+    storage::update_proposal(env, id, &proposal);
+
+    env.events().publish((symbol_short!("proposal"), symbol_short!("canceled")), id);
+    Ok(true)
+}
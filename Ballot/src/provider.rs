@@ -0,0 +1,9 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+// Contracts deciding who votes and with which weight, see the EligibilityProviders crate for reference ones
+#[allow(dead_code)]
+#[contractclient(name = "ProviderClient")]
+pub trait EligibilityProvider {
+    fn is_eligible(env: Env, addr: Address) -> bool;
+    fn weight(env: Env, addr: Address) -> i128;
+}
@@ -0,0 +1,73 @@
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+use crate::storage::{self, Method, Question};
+use crate::{add_weight, schulze, Error};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Answer {
+    pub question: u32,
+    pub choices: Vec<Symbol>
+}
+
+pub fn check_answer(question: &Question, choices: &Vec<Symbol>) -> Result<(), Error> {
+    let allowed = match question.method {
+        Method::Plurality => 1,
+        Method::Approval | Method::Ranked => question.options.len()
+    };
+
+    if choices.is_empty() || choices.len() > allowed {
+        return Err(Error::InvalidOption);
+    }
+
+    for (i, choice) in choices.iter().enumerate() {
+        if !question.options.contains(&choice) {
+            return Err(Error::InvalidOption);
+        }
+
+        if choices.slice(..i as u32).contains(&choice) {
+            return Err(Error::InvalidOption);
+        }
+    }
+
+    Ok(())
+}
+
+// Each answer carries the voter weight plus the weight delegated to the voter for that question
+pub fn record(env: &Env, voter: &Address, answers: &Vec<Answer>, weight: u32) -> Result<(), Error> {
+    // Answering nothing would still bind the delegations carried by the voter
+    if answers.is_empty() {
+        return Err(Error::InvalidOption);
+    }
+
+    let questions = storage::get_questions(env);
+    for answer in answers.iter() {
+        let question = match questions.get(answer.question) {
+            Some(q) => q,
+            None => return Err(Error::QuestionNotFound)
+        };
+
+        if storage::get_answer(env, answer.question, voter).is_some() {
+            return Err(Error::QuestionAlreadyAnswered);
+        }
+
+        check_answer(&question, &answer.choices)?;
+        // Ranked answers only count their first preference, the full ranking goes to the pairwise matrix
+        let counted = match question.method {
+            Method::Ranked => {
+                schulze::add_ranking(env, answer.question, &question.options, &answer.choices, weight)?;
+                answer.choices.slice(..1)
+            },
+            _ => answer.choices.clone()
+        };
+
+        for choice in counted.iter() {
+            let count = add_weight(weight, storage::get_question_votes_count(env, answer.question, &choice))?;
+            storage::update_question_count(env, answer.question, &choice, count);
+        }
+
+        storage::store_answer(env, answer.question, voter, &answer.choices);
+    }
+
+    Ok(())
+}
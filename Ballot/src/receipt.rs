@@ -0,0 +1,104 @@
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, Bytes, BytesN, Env, Symbol, Vec};
+
+use crate::storage;
+
+// Receipts are leaves of an append only Merkle tree with room for every u32 index,
+// a vote is proven with one sibling per level whatever the number of votes
+pub const DEPTH: u32 = 32;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Receipt {
+    pub index: u32,
+    pub leaf: BytesN<32>,
+    pub root: BytesN<32>
+}
+
+pub fn leaf_hash(env: &Env, index: u32, voter: &Address, candidate: &Symbol) -> BytesN<32> {
+    let mut data = Bytes::from_array(env, &index.to_be_bytes());
+    data.append(&voter.clone().to_xdr(env));
+    data.append(&candidate.clone().to_xdr(env));
+    env.crypto().sha256(&data)
+}
+
+// Unlike eligibility trees, positions matter here so pairs are hashed left to right
+pub fn hash_pair(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::from_array(env, &left.to_array());
+    data.extend_from_array(&right.to_array());
+    env.crypto().sha256(&data)
+}
+
+// Empty positions hold the zero leaf, hashed up to the level they sit at
+fn zero_leaf(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[0; 32])
+}
+
+// Only the last complete left subtree of each level (the branch) is kept to add the next leaf.
+// Returns the root of the tree holding the leaves up to index
+fn insert(env: &Env, branch: &mut Vec<BytesN<32>>, index: u32, leaf: &BytesN<32>) -> BytesN<32> {
+    let mut node = leaf.clone();
+    let mut zero = zero_leaf(env);
+    let mut stored = false;
+    for level in 0..DEPTH {
+        if (index >> level) & 1 == 0 {
+            // The first left child on the way up is a subtree the next leaves will need
+            if !stored {
+                if level < branch.len() {
+                    branch.set(level, node.clone());
+                } else {
+                    branch.push_back(node.clone());
+                }
+                stored = true;
+            }
+
+            node = hash_pair(env, &node, &zero);
+        } else {
+            node = hash_pair(env, &branch.get_unchecked(level), &node);
+        }
+
+        zero = hash_pair(env, &zero, &zero);
+    }
+
+    node
+}
+
+pub fn verify(env: &Env, index: u32, leaf: &BytesN<32>, proof: &Vec<BytesN<32>>, root: &BytesN<32>) -> bool {
+    if proof.len() != DEPTH {
+        return false;
+    }
+
+    let mut node = leaf.clone();
+    for (level, sibling) in proof.iter().enumerate() {
+        node = match (index >> level) & 1 {
+            0 => hash_pair(env, &node, &sibling),
+            _ => hash_pair(env, &sibling, &node)
+        };
+    }
+
+    node == *root
+}
+
+pub fn append(env: &Env, index: u32, voter: &Address, candidate: &Symbol) -> Receipt {
+    let leaf = leaf_hash(env, index, voter, candidate);
+    let mut branch = storage::get_branch(env);
+    let root = insert(env, &mut branch, index, &leaf);
+
+    storage::store_leaf(env, index, &leaf);
+    storage::update_branch(env, &branch);
+    storage::update_root(env, &root);
+
+    Receipt { index, leaf, root }
+}
+
+// Proofs are built off chain from the leaves, checking one only reads the receipt's own leaf
+pub fn verify_stored(env: &Env, receipt: &Receipt, proof: &Vec<BytesN<32>>) -> bool {
+    if receipt.index >= storage::get_voters_count(env) {
+        return false;
+    }
+
+    if storage::get_leaf(env, receipt.index) != Some(receipt.leaf.clone()) {
+        return false;
+    }
+
+    verify(env, receipt.index, &receipt.leaf, proof, &storage::get_root(env))
+}
@@ -0,0 +1,104 @@
+use soroban_sdk::{contracttype, Env, Symbol, Vec};
+
+use crate::storage;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CandidateResult {
+    pub candidate: Symbol,
+    pub votes: u32,
+    pub direct: u32,
+    pub delegated: u32,
+    pub share_bps: u32,
+    pub electorate_bps: u32
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Results {
+    pub candidates: Vec<CandidateResult>,
+    pub total: u64,
+    pub electorate: u32,
+    pub margin: u32,
+    pub winners: Vec<Symbol>
+}
+
+fn bps(part: u32, whole: u64) -> u32 {
+    match whole {
+        0 => 0,
+        _ => (part as u64 * 10000 / whole) as u32
+    }
+}
+
+// Every tally change goes through here so the results never need to read all the candidates.
+// Only the top count, the next count below it and how many candidates share the top are kept
+pub fn record(env: &Env, old: u32, new: u32) {
+    if new <= old {
+        return;
+    }
+
+    let mut lead = storage::get_lead(env);
+    if old == lead.top && lead.top > 0 {
+        if lead.tied > 1 {
+            lead.second = lead.top;
+            lead.tied = 1;
+        }
+        lead.top = new;
+    } else if new > lead.top {
+        lead.second = lead.top;
+        lead.top = new;
+        lead.tied = 1;
+    } else if new == lead.top {
+        lead.tied += 1;
+    } else {
+        lead.second = lead.second.max(new);
+    }
+
+    storage::update_lead(env, &lead);
+    storage::update_total_votes(env, storage::get_total_votes(env) + (new - old) as u64);
+}
+
+// Candidates of the requested page are ordered by votes, ties keep the order in which candidates first received a vote.
+// The candidate ballot is decided by plurality so every candidate sharing the top count is a winner, winners are
+// reported for the page while the total and the margin cover the whole ballot
+pub fn tally(env: &Env, electorate: u32, offset: u32, limit: u32) -> Results {
+    let lead = storage::get_lead(env);
+    let total = storage::get_total_votes(env);
+    let mut candidates: Vec<CandidateResult> = Vec::new(env);
+    let mut winners: Vec<Symbol> = Vec::new(env);
+    for candidate in storage::get_candidates_page(env, offset, limit).iter() {
+        let votes = storage::get_candidate_votes_count(env, &candidate);
+        let delegated = storage::get_candidate_delegated_count(env, &candidate);
+        let position = candidates
+            .iter()
+            .position(|c| c.votes < votes)
+            .unwrap_or(candidates.len() as usize) as u32
+        ;
+
+        if lead.top > 0 && votes == lead.top {
+            winners.push_back(candidate.clone());
+        }
+
+        candidates.insert(position, CandidateResult {
+            candidate,
+            votes,
+            direct: votes - delegated,
+            delegated,
+            share_bps: bps(votes, total),
+            electorate_bps: bps(votes, electorate as u64)
+        });
+    }
+
+    let margin = match lead.tied {
+        0 | 1 => lead.top - lead.second,
+        _ => 0
+    };
+
+    Results {
+        candidates,
+        total,
+        electorate,
+        margin,
+        winners
+    }
+}
@@ -0,0 +1,112 @@
+use soroban_sdk::{contracttype, Env, Map, Symbol, Vec};
+
+use crate::storage;
+use crate::{add_weight, Error};
+
+// Matrices are kept flat, row i holds the entries for option i against every option in question order
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RankedTally {
+    pub counts: Map<Symbol, u32>,
+    pub matrix: Vec<Vec<u32>>,
+    pub paths: Vec<Vec<u32>>,
+    pub order: Vec<Symbol>
+}
+
+fn rows(env: &Env, flat: &Vec<u32>, n: u32) -> Vec<Vec<u32>> {
+    let mut rows: Vec<Vec<u32>> = Vec::new(env);
+    for i in 0..n {
+        rows.push_back(flat.slice(i * n..(i + 1) * n));
+    }
+
+    rows
+}
+
+// Every ranked option is preferred to the ones ranked after it and to the unranked ones
+pub fn add_ranking(env: &Env, question: u32, options: &Vec<Symbol>, ranking: &Vec<Symbol>, weight: u32) -> Result<(), Error> {
+    let n = options.len();
+    let mut matrix = storage::get_pairwise(env, question, n);
+    let rank = |option: &Symbol| ranking.first_index_of(option).unwrap_or(n);
+    for (i, a) in options.iter().enumerate() {
+        for (j, b) in options.iter().enumerate() {
+            if i != j && rank(&a) < rank(&b) {
+                let idx = i as u32 * n + j as u32;
+                matrix.set(idx, add_weight(matrix.get_unchecked(idx), weight)?);
+            }
+        }
+    }
+
+    storage::update_pairwise(env, question, &matrix);
+    Ok(())
+}
+
+// Widest path between every pair of options, only links where the majority prefers i to j count
+pub fn strongest_paths(env: &Env, matrix: &Vec<u32>, n: u32) -> Vec<u32> {
+    let d = |i: u32, j: u32| matrix.get_unchecked(i * n + j);
+    let mut paths: Vec<u32> = Vec::new(env);
+    for i in 0..n {
+        for j in 0..n {
+            paths.push_back(if i != j && d(i, j) > d(j, i) { d(i, j) } else { 0 });
+        }
+    }
+
+    for i in 0..n {
+        for j in 0..n {
+            let ji = paths.get_unchecked(j * n + i);
+            if i == j || ji == 0 {
+                continue;
+            }
+
+            for k in 0..n {
+                if k == i || k == j {
+                    continue;
+                }
+
+                let through = ji.min(paths.get_unchecked(i * n + k));
+                if through > paths.get_unchecked(j * n + k) {
+                    paths.set(j * n + k, through);
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+// Options are ordered by how many others they beat on strongest paths, ties keep the question order
+pub fn order(env: &Env, options: &Vec<Symbol>, paths: &Vec<u32>) -> Vec<Symbol> {
+    let n = options.len();
+    let mut order: Vec<Symbol> = Vec::new(env);
+    let mut wins: Vec<u32> = Vec::new(env);
+    for i in 0..n {
+        let beaten = (0..n)
+            .filter(|&j| paths.get_unchecked(i * n + j) > paths.get_unchecked(j * n + i))
+            .count() as u32
+        ;
+
+        let position = wins.iter().position(|w| w < beaten).unwrap_or(wins.len() as usize) as u32;
+        wins.insert(position, beaten);
+        order.insert(position, options.get_unchecked(i));
+    }
+
+    order
+}
+
+pub fn tally(env: &Env, question: u32, options: &Vec<Symbol>) -> RankedTally {
+    let n = options.len();
+    let mut counts: Map<Symbol, u32> = Map::new(env);
+    for option in options.iter() {
+        let option_count = storage::get_question_votes_count(env, question, &option);
+        counts.set(option, option_count);
+    }
+
+    let matrix = storage::get_pairwise(env, question, n);
+    let paths = strongest_paths(env, &matrix, n);
+
+    RankedTally {
+        counts,
+        matrix: rows(env, &matrix, n),
+        order: order(env, options, &paths),
+        paths: rows(env, &paths, n)
+    }
+}
@@ -1,96 +1,668 @@
-use soroban_sdk::{ contracttype, symbol_short, Address, Env, Symbol, Vec};
+use soroban_sdk::{ contracttype, symbol_short, Address, BytesN, Env, IntoVal, Symbol, Val, Vec};
 
-pub const VOTES: Symbol = symbol_short!("votes");
-pub const PARTIES: Symbol = symbol_short!("parties");
+use crate::{validation, Error};
+
+pub const NPARTIES: Symbol = symbol_short!("nparties");
 pub const CONFIG: Symbol = symbol_short!("config");
 pub const TOKEN: Symbol = symbol_short!("token");
+pub const NDELEGS: Symbol = symbol_short!("ndelegs");
+pub const NACTIVE: Symbol = symbol_short!("nactive");
+pub const CARRIED: Symbol = symbol_short!("carried");
+pub const ROOT: Symbol = symbol_short!("root");
+pub const BRANCH: Symbol = symbol_short!("branch");
+pub const ADMIN: Symbol = symbol_short!("admin");
+pub const ELIGIB: Symbol = symbol_short!("eligib");
+pub const VOTERS: Symbol = symbol_short!("voters");
+pub const PARTICIP: Symbol = symbol_short!("particip");
+pub const VERSION: Symbol = symbol_short!("version");
+pub const RETAIN: Symbol = symbol_short!("retain");
+pub const QUESTS: Symbol = symbol_short!("quests");
+pub const RULES: Symbol = symbol_short!("rules");
+pub const PROPS: Symbol = symbol_short!("props");
+pub const DELAY: Symbol = symbol_short!("delay");
+pub const GUARD: Symbol = symbol_short!("guard");
+pub const STATUS: Symbol = symbol_short!("status");
+pub const REASON: Symbol = symbol_short!("reason");
+pub const PAUSED: Symbol = symbol_short!("paused");
+pub const SNAP: Symbol = symbol_short!("snap");
+pub const LEAD: Symbol = symbol_short!("lead");
+pub const TOTAL: Symbol = symbol_short!("total");
+
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const DEFAULT_RETENTION: u32 = 30 * DAY_IN_LEDGERS;
+
+// Ballots deployed before the upgrade entrypoint existed cannot run new code, versions count from the first upgradeable layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum WindowUnit {
+    Timestamp,
+    Ledger
+}
 
 #[derive(Debug)]
 #[contracttype]
 pub struct Config {
     pub from: u64,
-    pub to: u64
+    pub to: u64,
+    pub unit: WindowUnit
 }
 
 impl Default for Config {
     fn default () -> Config {
-        Config { from: 0, to: 0 }
+        Config { from: 0, to: 0, unit: WindowUnit::Timestamp }
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
-pub enum VCounter {
-    Counter(Symbol)
+pub struct MerkleRoot {
+    pub root: BytesN<32>,
+    pub voters: u32
 }
 
-pub fn get_candidates(env: &Env) -> Vec<Symbol> {
-    let pts: Vec<Symbol> = env
-        .storage()
-        .instance()
-        .get(&PARTIES)
-        .unwrap_or(Vec::new(env))
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Eligibility {
+    Token,
+    Merkle(MerkleRoot),
+    // Holders of at least this amount at the snapshot vote, the token must record balance checkpoints
+    Balance(i128),
+    // A contract implementing the eligibility provider interface decides who votes and with which weight
+    Provider(Address),
+    // Any standard token, voters transfer this amount to the ballot and get it back once it ends
+    Escrow(i128)
+}
+
+// Top candidate count, the highest count below it and how many candidates share the top
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct Lead {
+    pub top: u32,
+    pub second: u32,
+    pub tied: u32
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Delegation {
+    pub delegator: Address,
+    pub delegate: Address,
+    pub weight: u32,
+    pub expiration: u32
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Method {
+    Plurality,
+    Approval,
+    // Choices rank the options from most to least preferred, counts hold first preferences
+    Ranked
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Question {
+    pub title: Symbol,
+    pub options: Vec<Symbol>,
+    pub method: Method
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Guardian {
+    pub address: Address,
+    pub veto_window: u64
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BallotStatus {
+    Active,
+    Vetoed,
+    Canceled
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProposalRules {
+    pub min_balance: u32,
+    pub sponsors: u32,
+    pub max_per_address: u32
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ProposalStatus {
+    Pending,
+    Open,
+    Queued,
+    Executed,
+    Canceled
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct Call {
+    pub contract: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>
+}
+
+#[derive(Clone, Debug)]
+#[contracttype]
+pub struct Proposal {
+    pub proposer: Address,
+    pub title: Symbol,
+    pub description: BytesN<32>,
+    pub options: Vec<Symbol>,
+    pub sponsors: Vec<Address>,
+    pub status: ProposalStatus,
+    pub question: Option<u32>,
+    pub actions: Vec<Call>,
+    pub eta: u64
+}
+
+#[contracttype]
+pub enum VRecord {
+    Vote(Address),
+    Leaf(u32),
+    Voter(u32),
+    Tally(Symbol),
+    Answered(Address),
+    Answer(u32, Address),
+    QTally(u32, Symbol),
+    Proposal(u32),
+    Proposals(Address),
+    DTally(Symbol),
+    Carried(Address),
+    CarriedBy(Address),
+    Pairwise(u32),
+    Delegation(u32),
+    DelegatedFrom(Address),
+    DelegatedTo(Address),
+    Party(u32),
+    PartyIndex(Symbol),
+    Escrow(Address)
+}
+
+pub fn get_candidates_count(env: &Env) -> u32 {
+    env.storage().instance().get(&NPARTIES).unwrap_or(0)
+}
+
+pub fn get_candidate(env: &Env, index: u32) -> Option<Symbol> {
+    env.storage().persistent().get(&VRecord::Party(index))
+}
+
+pub fn get_candidates_page(env: &Env, offset: u32, limit: u32) -> Vec<Symbol> {
+    let total = get_candidates_count(env);
+    let start = offset.min(total);
+    let end = start
+        .saturating_add(limit.min(MAX_PAGE_SIZE))
+        .min(total)
     ;
 
+    let mut pts: Vec<Symbol> = Vec::new(env);
+    for i in start..end {
+        pts.push_back(get_candidate(env, i).unwrap());
+    }
+
     pts
 }
 
 pub fn store_party(env: &Env, p: &Symbol) -> bool {
-    let mut pts: Vec<Symbol> = get_candidates(env);
-    if !pts.contains(p) {
-        pts.push_back(p.clone());
-        env.storage().instance().set(&PARTIES, &pts);
-        return true;
+    let index_key = VRecord::PartyIndex(p.clone());
+    if env.storage().persistent().has(&index_key) {
+        return false;
     }
 
-    false
+    let index = get_candidates_count(env);
+    set_persistent(env, &VRecord::Party(index), p);
+    set_persistent(env, &index_key, &index);
+    env.storage().instance().set(&NPARTIES, &(index + 1));
+    true
 }
 
-pub fn get_votes(env: &Env) -> Vec<Address>{
-    let vts: Vec<Address> = env
-        .storage()
+pub fn get_retention(env: &Env) -> u32 {
+    env.storage().instance().get(&RETAIN).unwrap_or(DEFAULT_RETENTION)
+}
+
+pub fn update_retention(env: &Env, retention: u32) {
+    env.storage().instance().set(&RETAIN, &retention);
+}
+
+// Data has to outlive the voting window plus the retention period kept for audits, as far as the network allows
+pub fn ttl_amount(env: &Env) -> u32 {
+    validation::remaining_ledgers(env, &get_config(env))
+        .saturating_add(get_retention(env))
+        .min(env.storage().max_ttl())
+}
+
+pub fn bump_instance(env: &Env) {
+    let amount = ttl_amount(env);
+    env.storage()
         .instance()
-        .get(&VOTES)
-        .unwrap_or(Vec::new(env))
+        .extend_ttl(amount, amount);
+}
+
+pub fn bump_persistent<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    if env.storage().persistent().has(key) {
+        let amount = ttl_amount(env);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, amount, amount);
+    }
+}
+
+fn set_persistent<K: IntoVal<Env, Val>, V: IntoVal<Env, Val>>(env: &Env, key: &K, val: &V) {
+    env.storage().persistent().set(key, val);
+    bump_persistent(env, key);
+}
+
+pub fn get_voters_count(env: &Env) -> u32 {
+    env.storage().instance().get(&VOTERS).unwrap_or(0)
+}
+
+pub fn get_voter(env: &Env, index: u32) -> Option<Address> {
+    env.storage().persistent().get(&VRecord::Voter(index))
+}
+
+pub fn store_voter(env: &Env, voter: &Address) -> u32 {
+    let index = get_voters_count(env);
+    set_persistent(env, &VRecord::Voter(index), voter);
+    env.storage().instance().set(&VOTERS, &(index + 1));
+    index
+}
+
+// Everyone who voted for a candidate or answered a question, counted once
+pub fn get_participants_count(env: &Env) -> u32 {
+    env.storage().instance().get(&PARTICIP).unwrap_or(0)
+}
+
+pub fn add_participant(env: &Env) {
+    env.storage().instance().set(&PARTICIP, &(get_participants_count(env) + 1));
+}
+
+pub fn get_voters(env: &Env, offset: u32, limit: u32) -> Vec<Address> {
+    let total = get_voters_count(env);
+    let start = offset.min(total);
+    let end = start
+        .saturating_add(limit.min(MAX_PAGE_SIZE))
+        .min(total)
     ;
 
-    vts
+    let mut voters: Vec<Address> = Vec::new(env);
+    for i in start..end {
+        voters.push_back(get_voter(env, i).unwrap());
+    }
+
+    voters
+}
+
+pub fn get_candidate_votes_count(env: &Env, candidate: &Symbol) -> u32 {
+    env.storage().persistent().get(&VRecord::Tally(candidate.clone())).unwrap_or(0)
+}
+
+pub fn update_candidate_count(env: &Env, candidate: &Symbol, count: u32) {
+    set_persistent(env, &VRecord::Tally(candidate.clone()), &count);
+}
+
+pub fn get_lead(env: &Env) -> Lead {
+    env.storage().instance().get(&LEAD).unwrap_or_default()
+}
+
+pub fn update_lead(env: &Env, lead: &Lead) {
+    env.storage().instance().set(&LEAD, lead);
+}
+
+// Sum of every candidate count, which may not fit a u32
+pub fn get_total_votes(env: &Env) -> u64 {
+    env.storage().instance().get(&TOTAL).unwrap_or(0)
+}
+
+pub fn update_total_votes(env: &Env, total: u64) {
+    env.storage().instance().set(&TOTAL, &total);
+}
+
+// Share of the candidate count that was carried by delegates
+pub fn get_candidate_delegated_count(env: &Env, candidate: &Symbol) -> u32 {
+    env.storage().persistent().get(&VRecord::DTally(candidate.clone())).unwrap_or(0)
+}
+
+pub fn update_candidate_delegated_count(env: &Env, candidate: &Symbol, count: u32) {
+    set_persistent(env, &VRecord::DTally(candidate.clone()), &count);
+}
+
+pub fn get_voter_candidate(env: &Env, voter: &Address) -> Option<Symbol> {
+    env.storage().persistent().get(&VRecord::Vote(voter.clone()))
+}
+
+pub fn store_voter_candidate(env: &Env, voter: &Address, candidate: &Symbol) {
+    set_persistent(env, &VRecord::Vote(voter.clone()), candidate);
+}
+
+// Delegators whose weight went along with the voter's candidate
+pub fn get_carried_delegators(env: &Env, voter: &Address) -> Vec<Address> {
+    env.storage().persistent().get(&VRecord::Carried(voter.clone())).unwrap_or(Vec::new(env))
 }
 
-pub fn get_candidate_votes_count(env: &Env, candidate: &VCounter) -> u32 {
-    let total_votes = env.storage().instance().get(candidate).unwrap_or(0);
-    total_votes
+pub fn store_carried_delegators(env: &Env, voter: &Address, delegators: &Vec<Address>) {
+    set_persistent(env, &VRecord::Carried(voter.clone()), delegators);
+    for delegator in delegators.iter() {
+        set_persistent(env, &VRecord::CarriedBy(delegator), voter);
+    }
 }
 
-pub fn update_candidate_count(env: &Env, candidate: VCounter, count: u32) {
-    env.storage().instance().set(&candidate, &count);
+pub fn get_carrier(env: &Env, delegator: &Address) -> Option<Address> {
+    env.storage().persistent().get(&VRecord::CarriedBy(delegator.clone()))
 }
 
-pub fn update_votes(env: &Env, votes: Vec<Address>) {
-    env.storage().instance().set(&VOTES, &votes);
+// Configuring writes the version, a ballot without one holds nothing to migrate
+pub fn get_version(env: &Env) -> u32 {
+    env.storage().instance().get(&VERSION).unwrap_or(SCHEMA_VERSION)
 }
 
-pub fn store_config(env: &Env, ts_start: u64, ts_end: u64, token: Address) {
+pub fn update_version(env: &Env, version: u32) {
+    env.storage().instance().set(&VERSION, &version);
+}
+
+pub fn store_config(env: &Env, admin: Address, start: u64, end: u64, unit: WindowUnit, token: Option<Address>) {
     let cfg = Config {
-        from: ts_start,
-        to: ts_end
+        from: start,
+        to: end,
+        unit
     };
 
+    env.storage().instance().set(&ADMIN, &admin);
+    env.storage().instance().set(&VERSION, &SCHEMA_VERSION);
     env.storage().instance().set(&CONFIG, &cfg);
-    env.storage().instance().set(&TOKEN, &token)
+    match token {
+        Some(token) => env.storage().instance().set(&TOKEN, &token),
+        None => env.storage().instance().remove(&TOKEN)
+    }
+    env.storage().instance().remove(&SNAP);
+}
+
+pub fn get_snapshot(env: &Env) -> Option<u32> {
+    env.storage().instance().get(&SNAP)
+}
+
+pub fn store_snapshot(env: &Env, ledger: u32) {
+    env.storage().instance().set(&SNAP, &ledger);
+}
+
+pub fn get_admin(env: &Env) -> Result<Address, Error> {
+    env.storage().instance().get(&ADMIN).ok_or(Error::NotConfigured)
+}
+
+pub fn get_eligibility(env: &Env) -> Eligibility {
+    env
+        .storage()
+        .instance()
+        .get(&ELIGIB)
+        .unwrap_or(Eligibility::Token)
+}
+
+pub fn store_eligibility(env: &Env, eligibility: &Eligibility) {
+    env.storage().instance().set(&ELIGIB, eligibility);
+}
+
+pub fn update_config(env: &Env, cfg: &Config) {
+    env.storage().instance().set(&CONFIG, cfg);
+}
+
+pub fn get_paused_at(env: &Env) -> Option<u64> {
+    env.storage().instance().get(&PAUSED)
+}
+
+pub fn update_paused_at(env: &Env, paused_at: Option<u64>) {
+    match paused_at {
+        Some(p) => env.storage().instance().set(&PAUSED, &p),
+        None => env.storage().instance().remove(&PAUSED)
+    }
 }
 
 pub fn get_config(env: &Env) -> Config {
-    let cfg = env
+    env
         .storage()
         .instance()
         .get(&CONFIG)
-        .unwrap_or(Config::default())
+        .unwrap_or_default()
+}
+
+pub fn get_token(env: &Env) -> Result<Address, Error> {
+    env.storage().instance().get(&TOKEN).ok_or(Error::NotConfigured)
+}
+
+pub fn get_questions(env: &Env) -> Vec<Question> {
+    env
+        .storage()
+        .instance()
+        .get(&QUESTS)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn store_question(env: &Env, question: Question) -> u32 {
+    let mut questions = get_questions(env);
+    questions.push_back(question);
+    env.storage().instance().set(&QUESTS, &questions);
+    questions.len() - 1
+}
+
+pub fn get_answer(env: &Env, question: u32, voter: &Address) -> Option<Vec<Symbol>> {
+    env.storage().persistent().get(&VRecord::Answer(question, voter.clone()))
+}
+
+pub fn store_answer(env: &Env, question: u32, voter: &Address, choices: &Vec<Symbol>) {
+    set_persistent(env, &VRecord::Answer(question, voter.clone()), choices);
+    set_persistent(env, &VRecord::Answered(voter.clone()), &true);
+}
+
+pub fn has_answered(env: &Env, voter: &Address) -> bool {
+    env.storage().persistent().has(&VRecord::Answered(voter.clone()))
+}
+
+pub fn get_question_votes_count(env: &Env, question: u32, option: &Symbol) -> u32 {
+    env.storage().persistent().get(&VRecord::QTally(question, option.clone())).unwrap_or(0)
+}
+
+pub fn update_question_count(env: &Env, question: u32, option: &Symbol, count: u32) {
+    set_persistent(env, &VRecord::QTally(question, option.clone()), &count);
+}
+
+pub fn get_pairwise(env: &Env, question: u32, options: u32) -> Vec<u32> {
+    env
+        .storage()
+        .persistent()
+        .get(&VRecord::Pairwise(question))
+        .unwrap_or_else(|| {
+            let mut matrix = Vec::new(env);
+            for _ in 0..options * options {
+                matrix.push_back(0);
+            }
+            matrix
+        })
+}
+
+pub fn update_pairwise(env: &Env, question: u32, matrix: &Vec<u32>) {
+    set_persistent(env, &VRecord::Pairwise(question), matrix);
+}
+
+pub fn get_proposal_rules(env: &Env) -> Option<ProposalRules> {
+    env.storage().instance().get(&RULES)
+}
+
+pub fn store_proposal_rules(env: &Env, rules: &ProposalRules) {
+    env.storage().instance().set(&RULES, rules);
+}
+
+pub fn get_timelock_delay(env: &Env) -> u64 {
+    env.storage().instance().get(&DELAY).unwrap_or(0)
+}
+
+pub fn update_timelock_delay(env: &Env, delay: u64) {
+    env.storage().instance().set(&DELAY, &delay);
+}
+
+pub fn get_guardian(env: &Env) -> Option<Guardian> {
+    env.storage().instance().get(&GUARD)
+}
+
+pub fn store_guardian(env: &Env, guardian: &Guardian) {
+    env.storage().instance().set(&GUARD, guardian);
+}
+
+pub fn get_ballot_status(env: &Env) -> BallotStatus {
+    env.storage().instance().get(&STATUS).unwrap_or(BallotStatus::Active)
+}
+
+pub fn update_ballot_status(env: &Env, status: BallotStatus, reason: &BytesN<32>) {
+    env.storage().instance().set(&STATUS, &status);
+    env.storage().instance().set(&REASON, reason);
+}
+
+pub fn get_status_reason(env: &Env) -> Option<BytesN<32>> {
+    env.storage().instance().get(&REASON)
+}
+
+pub fn get_proposals_count(env: &Env) -> u32 {
+    env.storage().instance().get(&PROPS).unwrap_or(0)
+}
+
+pub fn get_proposal(env: &Env, id: u32) -> Option<Proposal> {
+    env.storage().persistent().get(&VRecord::Proposal(id))
+}
+
+pub fn update_proposal(env: &Env, id: u32, proposal: &Proposal) {
+    set_persistent(env, &VRecord::Proposal(id), proposal);
+}
+
+pub fn store_proposal(env: &Env, proposal: &Proposal) -> u32 {
+    let id = get_proposals_count(env);
+    update_proposal(env, id, proposal);
+    env.storage().instance().set(&PROPS, &(id + 1));
+
+    let by_proposer = get_proposer_count(env, &proposal.proposer) + 1;
+    set_persistent(env, &VRecord::Proposals(proposal.proposer.clone()), &by_proposer);
+    id
+}
+
+pub fn get_proposer_count(env: &Env, proposer: &Address) -> u32 {
+    env.storage().persistent().get(&VRecord::Proposals(proposer.clone())).unwrap_or(0)
+}
+
+pub fn get_root(env: &Env) -> BytesN<32> {
+    env
+        .storage()
+        .instance()
+        .get(&ROOT)
+        .unwrap_or(BytesN::from_array(env, &[0; 32]))
+}
+
+pub fn update_root(env: &Env, root: &BytesN<32>) {
+    env.storage().instance().set(&ROOT, root);
+}
+
+pub fn get_branch(env: &Env) -> Vec<BytesN<32>> {
+    env
+        .storage()
+        .instance()
+        .get(&BRANCH)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn update_branch(env: &Env, branch: &Vec<BytesN<32>>) {
+    env.storage().instance().set(&BRANCH, branch);
+}
+
+pub fn get_leaf(env: &Env, index: u32) -> Option<BytesN<32>> {
+    env.storage().persistent().get(&VRecord::Leaf(index))
+}
+
+pub fn store_leaf(env: &Env, index: u32, leaf: &BytesN<32>) {
+    set_persistent(env, &VRecord::Leaf(index), leaf);
+}
+
+pub fn get_delegations_count(env: &Env) -> u32 {
+    env.storage().instance().get(&NDELEGS).unwrap_or(0)
+}
+
+pub fn get_active_delegations_count(env: &Env) -> u32 {
+    env.storage().instance().get(&NACTIVE).unwrap_or(0)
+}
+
+pub fn get_delegation(env: &Env, index: u32) -> Option<Delegation> {
+    env.storage().persistent().get(&VRecord::Delegation(index))
+}
+
+// Delegations are indexed for listing and keyed by both ends for lookups, the latest one wins on each end
+pub fn store_delegation(env: &Env, delegation: Delegation) -> u32 {
+    let index = get_delegations_count(env);
+    // A delegator only delegates again once its previous delegation lapsed unused, which no longer counts as active
+    if get_delegation_from(env, &delegation.delegator).is_none() {
+        env.storage().instance().set(&NACTIVE, &(get_active_delegations_count(env) + 1));
+    }
+    set_persistent(env, &VRecord::Delegation(index), &delegation);
+    set_persistent(env, &VRecord::DelegatedFrom(delegation.delegator.clone()), &delegation);
+    set_persistent(env, &VRecord::DelegatedTo(delegation.delegate.clone()), &delegation);
+    env.storage().instance().set(&NDELEGS, &(index + 1));
+    index
+}
+
+pub fn get_delegations(env: &Env, offset: u32, limit: u32) -> Vec<Delegation> {
+    let total = get_delegations_count(env);
+    let start = offset.min(total);
+    let end = start
+        .saturating_add(limit.min(MAX_PAGE_SIZE))
+        .min(total)
     ;
 
-    cfg
+    let mut delegations: Vec<Delegation> = Vec::new(env);
+    for i in start..end {
+        delegations.push_back(get_delegation(env, i).unwrap());
+    }
+
+    delegations
+}
+
+pub fn get_delegation_from(env: &Env, delegator: &Address) -> Option<Delegation> {
+    env.storage().persistent().get(&VRecord::DelegatedFrom(delegator.clone()))
+}
+
+pub fn get_delegation_to(env: &Env, delegate: &Address) -> Option<Delegation> {
+    env.storage().persistent().get(&VRecord::DelegatedTo(delegate.clone()))
+}
+
+pub fn get_active_delegation_from(env: &Env, delegator: &Address) -> Option<Delegation> {
+    get_delegation_from(env, delegator).filter(|d| d.expiration >= env.ledger().sequence())
+}
+
+pub fn get_active_delegation_to(env: &Env, delegate: &Address) -> Option<Delegation> {
+    get_delegation_to(env, delegate).filter(|d| d.expiration >= env.ledger().sequence())
+}
+
+// Number of delegators whose weight was cast by their delegate
+pub fn get_carried_votes(env: &Env) -> u32 {
+    env.storage().instance().get(&CARRIED).unwrap_or(0)
 }
 
-pub fn get_token(env: &Env) -> Address {
-    let token: Address = env.storage().instance().get(&TOKEN).unwrap();
-    token
-}
\ No newline at end of file
+pub fn update_carried_votes(env: &Env, carried: u32) {
+    env.storage().instance().set(&CARRIED, &carried);
+}
+
+// Tokens an escrow ballot holds on behalf of addr until it ends
+pub fn get_escrow(env: &Env, addr: &Address) -> i128 {
+    env.storage().persistent().get(&VRecord::Escrow(addr.clone())).unwrap_or(0)
+}
+
+pub fn store_escrow(env: &Env, addr: &Address, amount: i128) {
+    set_persistent(env, &VRecord::Escrow(addr.clone()), &amount);
+}
+
+pub fn remove_escrow(env: &Env, addr: &Address) {
+    env.storage().persistent().remove(&VRecord::Escrow(addr.clone()));
+}
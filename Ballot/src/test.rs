@@ -1,7 +1,50 @@
 #![cfg(test)]
 
-use super::{token, Ballot, BallotClient};
-use soroban_sdk::{symbol_short, testutils::{Address as _, Ledger}, Address, Env, Vec};
+use super::{Answer, Ballot, BallotClient, CandidateResult, DelegatedVote, Error, RankedTally, Results, VoteRecord, DelegationTarget, Receipt, Stats, VoteChoice, VoterStatus};
+use super::{merkle, receipt};
+use super::validation::EXTRA_LEDGERS;
+use super::storage::{BallotStatus, Call, Delegation, Guardian, Eligibility, MerkleRoot, Method, ProposalRules, ProposalStatus, VRecord, WindowUnit, SCHEMA_VERSION};
+use soroban_sdk::{contract, contractimpl, symbol_short, testutils::{Address as _, Events, Ledger}, vec, xdr, Address, BytesN, Env, IntoVal, Symbol, Vec};
+
+mod token {
+    soroban_sdk::contractimport!(
+        file = "../BallotToken/target/wasm32-unknown-unknown/release/ballot_token.wasm"
+    );
+}
+
+#[contract]
+pub struct Target;
+
+#[contractimpl]
+impl Target {
+    pub fn set(env: Env, value: u32) -> u32 {
+        env.storage().instance().set(&symbol_short!("value"), &value);
+        value
+    }
+
+    pub fn value(env: Env) -> u32 {
+        env.storage().instance().get(&symbol_short!("value")).unwrap_or(0)
+    }
+}
+
+// Eligibility provider whose weights are set by the test, zero meaning not eligible
+#[contract]
+pub struct MockProvider;
+
+#[contractimpl]
+impl MockProvider {
+    pub fn set_weight(env: Env, addr: Address, weight: i128) {
+        env.storage().instance().set(&addr, &weight);
+    }
+
+    pub fn is_eligible(env: Env, addr: Address) -> bool {
+        Self::weight(env, addr) > 0
+    }
+
+    pub fn weight(env: Env, addr: Address) -> i128 {
+        env.storage().instance().get(&addr).unwrap_or(0)
+    }
+}
 
 struct TestData<'a> {
     admin: Address,
@@ -9,19 +52,30 @@ struct TestData<'a> {
     token: token::Client<'a>
 }
 
-fn get_test_data(env: &Env, num_voters: u8) -> TestData {
-    let admin = Address::generate(&env);
-    let mut voters = Vec::new(&env);
-    let token_address = env.register_contract_wasm(None, token::WASM);
-    let token = token::Client::new(&env, &token_address);
-    token.initialize(&admin);
+fn get_test_data<'a>(env: &'a Env, ballot: &Address, num_voters: u8) -> TestData<'a> {
+    let admin = Address::generate(env);
+    let mut voters = Vec::new(env);
+    let deployer = Address::generate(env);
+    let salt = BytesN::from_array(env, &[0; 32]);
+    let token_address = env.deployer().with_address(deployer.clone(), salt.clone()).deployed_address();
+    env.register_contract_wasm(&token_address, token::WASM);
+    let token = token::Client::new(env, &token_address);
+    token.initialize(&admin, &deployer, &salt);
+    token.set_locker(ballot, &true);
 
+    // Voters hold their token before the ledger the test starts at, which is the earliest a snapshot can be
+    let sequence = env.ledger().sequence();
+    env.ledger().with_mut(|l| {l.sequence_number = sequence.saturating_sub(1);});
     for _i in 0..num_voters {
-        let addr: Address = Address::generate(&env);
+        let addr: Address = Address::generate(env);
         token.mint(&addr);
         voters.push_back(addr);
     }
+    env.ledger().with_mut(|l| {l.sequence_number = sequence;});
 
+    // The test env shares one budget across calls while on chain each transaction gets its own,
+    // so the token setup is not charged to the ballot calls under test
+    env.budget().reset_default();
     TestData {
         admin,
         voters,
@@ -29,30 +83,84 @@ fn get_test_data(env: &Env, num_voters: u8) -> TestData {
     }
 }
 
+// Siblings of the leaf at index in the receipts tree, level by level, as a voter would build them from the vote leaves
+fn receipt_proof(env: &Env, leaves: &Vec<BytesN<32>>, index: u32) -> Vec<BytesN<32>> {
+    let mut proof = Vec::new(env);
+    let mut level = leaves.clone();
+    let mut zero = BytesN::from_array(env, &[0; 32]);
+    let mut index = index;
+    for _ in 0..receipt::DEPTH {
+        proof.push_back(level.get(index ^ 1).unwrap_or(zero.clone()));
+        let mut parents = Vec::new(env);
+        for i in (0..level.len()).step_by(2) {
+            parents.push_back(receipt::hash_pair(env, &level.get_unchecked(i), &level.get(i + 1).unwrap_or(zero.clone())));
+        }
+
+        level = parents;
+        zero = receipt::hash_pair(env, &zero, &zero);
+        index /= 2;
+    }
+
+    proof
+}
+
+struct MerkleData {
+    voters: Vec<Address>,
+    root: BytesN<32>,
+    proofs: Vec<Vec<BytesN<32>>>
+}
+
+// Builds a four leaves tree where the voter at position i has weight i + 1
+fn get_merkle_data(env: &Env) -> MerkleData {
+    let mut voters = Vec::new(env);
+    let mut leaves: Vec<BytesN<32>> = Vec::new(env);
+    for i in 0..4 {
+        let addr = Address::generate(env);
+        leaves.push_back(merkle::leaf_hash(env, &addr, i + 1));
+        voters.push_back(addr);
+    }
+
+    let n01 = merkle::hash_pair(env, &leaves.get(0).unwrap(), &leaves.get(1).unwrap());
+    let n23 = merkle::hash_pair(env, &leaves.get(2).unwrap(), &leaves.get(3).unwrap());
+    let proofs = vec![
+        env,
+        vec![env, leaves.get(1).unwrap(), n23.clone()],
+        vec![env, leaves.get(0).unwrap(), n23.clone()],
+        vec![env, leaves.get(3).unwrap(), n01.clone()],
+        vec![env, leaves.get(2).unwrap(), n01.clone()]
+    ];
+
+    MerkleData {
+        voters,
+        root: merkle::hash_pair(env, &n01, &n23),
+        proofs
+    }
+}
+
 #[test]
 fn vote_test() {
     let env = Env::default();
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 5);
+    let test_data = get_test_data(&env, &client.address, 5);
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
     let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
-    assert_eq!(client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist")), true);
-    assert_eq!(client.vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Conserv")), true);
-    assert_eq!(client.vote(&test_data.voters.get(2).unwrap(), &symbol_short!("Conserv")), true);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    client.vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Conserv"));
+    client.vote(&test_data.voters.get(2).unwrap(), &symbol_short!("Conserv"));
 
-    let count = client.count(&test_data.admin);
+    let count = client.count(&test_data.admin, &0, &10);
 
     assert_eq!(count.get(symbol_short!("Laborist")).unwrap(), 1);
     assert_eq!(count.get(symbol_short!("Conserv")).unwrap(), 2);
 
     client.delegate(&test_data.voters.get(3).unwrap(), &test_data.voters.get(4).unwrap());
-    assert_eq!(client.vote(&&test_data.voters.get(4).unwrap(), &symbol_short!("Conserv")), true);
+    client.vote(&test_data.voters.get(4).unwrap(), &symbol_short!("Conserv"));
 
-    let count = client.count(&test_data.admin);
+    let count = client.count(&test_data.admin, &0, &10);
 
     assert_eq!(count.get(symbol_short!("Laborist")).unwrap(), 1);
     assert_eq!(count.get(symbol_short!("Conserv")).unwrap(),4);
@@ -64,12 +172,12 @@ fn vote_test() {
 fn vote_out_of_dates_test() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 1);
+    let test_data = get_test_data(&env, &client.address, 1);
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
     let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
 }
 
@@ -78,14 +186,14 @@ fn vote_out_of_dates_test() {
 fn vote_test_already_voted() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 1);
+    let test_data = get_test_data(&env, &client.address, 1);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
     let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
 
     let voter = test_data.voters.get(0).unwrap();
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.vote(&voter, &symbol_short!("Laborist"));
     client.vote(&voter, &symbol_short!("Laborist"));
 }
@@ -95,7 +203,7 @@ fn vote_test_already_voted() {
 fn voter_cannot_delegate_since_does_not_hold_token() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 0);
+    let test_data = get_test_data(&env, &client.address, 0);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -103,7 +211,7 @@ fn voter_cannot_delegate_since_does_not_hold_token() {
     let voter1 = Address::generate(&env);
     let voter2 = Address::generate(&env);
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.delegate(&voter1, &voter2);
 }
 
@@ -112,7 +220,7 @@ fn voter_cannot_delegate_since_does_not_hold_token() {
 fn voter_cannot_delegate_since_target_does_not_hold_token() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 0);
+    let test_data = get_test_data(&env, &client.address, 0);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -121,7 +229,7 @@ fn voter_cannot_delegate_since_target_does_not_hold_token() {
     let voter2 = Address::generate(&env);
     
     test_data.token.mint(&voter1);
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.delegate(&voter1, &voter2);
 }
 
@@ -130,7 +238,7 @@ fn voter_cannot_delegate_since_target_does_not_hold_token() {
 fn voter_cannot_delegate_since_it_has_voted() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 2);
+    let test_data = get_test_data(&env, &client.address, 2);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -138,7 +246,7 @@ fn voter_cannot_delegate_since_it_has_voted() {
     let voter1 = test_data.voters.get(0).unwrap();
     let voter2 = test_data.voters.get(1).unwrap();
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.vote(&voter1, &symbol_short!("Laborist"));
     client.delegate(&voter1, &voter2);
 }
@@ -148,7 +256,7 @@ fn voter_cannot_delegate_since_it_has_voted() {
 fn vote_cannot_be_delegated_since_target_has_voted() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 2);
+    let test_data = get_test_data(&env, &client.address, 2);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -156,7 +264,7 @@ fn vote_cannot_be_delegated_since_target_has_voted() {
     let voter1 = test_data.voters.get(0).unwrap();
     let voter2 = test_data.voters.get(1).unwrap();
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.vote(&voter2, &symbol_short!("Laborist"));
     client.delegate(&voter1, &voter2);
 }
@@ -166,7 +274,7 @@ fn vote_cannot_be_delegated_since_target_has_voted() {
 fn voter_has_already_delegated_its_vote() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 2);
+    let test_data = get_test_data(&env, &client.address, 2);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -174,7 +282,7 @@ fn voter_has_already_delegated_its_vote() {
     let voter1 = test_data.voters.get(0).unwrap();
     let voter2 = test_data.voters.get(1).unwrap();
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.delegate(&voter1, &voter2);
     client.delegate(&voter1, &voter2);
 }
@@ -184,7 +292,7 @@ fn voter_has_already_delegated_its_vote() {
 fn voter_target_has_already_a_delegated_vote() {
     let env = Env::default();
     let client = create_client(&env);
-    let test_data = get_test_data(&env, 3);
+    let test_data = get_test_data(&env, &client.address, 3);
     env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
 
     let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
@@ -193,15 +301,1219 @@ fn voter_target_has_already_a_delegated_vote() {
     let voter2 = test_data.voters.get(1).unwrap();
     let voter3 = test_data.voters.get(2).unwrap();
 
-    client.configure(&test_data.admin, &test_data.token.address, &ts_start, &ts_end);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
     client.delegate(&voter1, &voter2);
     client.delegate(&voter3, &voter2);
 }
 
-fn create_client(env: &Env) -> BallotClient{
+#[test]
+fn vote_ledger_window_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 150;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &2000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    client.delegate(&test_data.voters.get(1).unwrap(), &test_data.voters.get(2).unwrap());
+
+    env.ledger().with_mut(|l| {l.sequence_number = 2000;});
+    client.vote(&test_data.voters.get(2).unwrap(), &symbol_short!("Conserv"));
+
+    let count = client.count(&test_data.admin, &0, &10);
+    assert_eq!(count.get(symbol_short!("Laborist")).unwrap(), 1);
+    assert_eq!(count.get(symbol_short!("Conserv")).unwrap(), 2);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #6)")]
+fn vote_out_of_ledger_window_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 150;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &2000, &WindowUnit::Ledger, &Eligibility::Token);
+    env.ledger().with_mut(|l| {l.sequence_number = 2001;});
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+}
+
+#[test]
+fn stats_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 5);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    client.vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Conserv"));
+    client.delegate(&test_data.voters.get(2).unwrap(), &test_data.voters.get(3).unwrap());
+
+    assert_eq!(client.stats(), Stats { holders: 5, voted: 2, delegations: 1, delegated_votes: 0, abstentions: 3, turnout_bps: 4000 });
+
+    env.budget().reset_default();
+    client.vote(&test_data.voters.get(3).unwrap(), &symbol_short!("Conserv"));
+    assert_eq!(client.stats(), Stats { holders: 5, voted: 3, delegations: 1, delegated_votes: 1, abstentions: 1, turnout_bps: 8000 });
+
+    // Answering questions counts as taking part, once per voter
+    let question = client.add_question(&symbol_short!("budget"), &vec![&env, symbol_short!("yes"), symbol_short!("no")], &Method::Plurality);
+    env.budget().reset_default();
+    client.vote_questions(&test_data.voters.get(4).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+    client.vote_questions(&test_data.voters.get(0).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("no")] }]);
+    assert_eq!(client.stats(), Stats { holders: 5, voted: 4, delegations: 1, delegated_votes: 1, abstentions: 0, turnout_bps: 10000 });
+
+    // Delegations whose weight was cast survive the window for post-ballot audits
+    env.ledger().with_mut(|l| {l.timestamp = ts_end + 1; l.sequence_number += 100_000;});
+    assert_eq!(client.stats().delegations, 1);
+    assert_eq!(client.voter_status(&test_data.voters.get(3).unwrap()).delegators, vec![&env, test_data.voters.get(2).unwrap()]);
+    assert_eq!(client.voter_status(&test_data.voters.get(2).unwrap()).delegate, DelegationTarget::Delegate(test_data.voters.get(3).unwrap()));
+}
+
+#[test]
+fn results_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 6);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    client.delegate(&test_data.voters.get(1).unwrap(), &test_data.voters.get(2).unwrap());
+    client.vote(&test_data.voters.get(2).unwrap(), &symbol_short!("Conserv"));
+    client.vote(&test_data.voters.get(3).unwrap(), &symbol_short!("Green"));
+
+    let results = client.results(&test_data.admin, &0, &10);
+    assert_eq!(results, Results {
+        candidates: vec![
+            &env,
+            CandidateResult { candidate: symbol_short!("Conserv"), votes: 2, direct: 1, delegated: 1, share_bps: 5000, electorate_bps: 3333 },
+            CandidateResult { candidate: symbol_short!("Laborist"), votes: 1, direct: 1, delegated: 0, share_bps: 2500, electorate_bps: 1666 },
+            CandidateResult { candidate: symbol_short!("Green"), votes: 1, direct: 1, delegated: 0, share_bps: 2500, electorate_bps: 1666 }
+        ],
+        total: 4,
+        electorate: 6,
+        margin: 1,
+        winners: vec![&env, symbol_short!("Conserv")]
+    });
+
+    // Later pages keep the ballot wide total and margin
+    let results = client.results(&test_data.admin, &2, &10);
+    assert_eq!(results.candidates, vec![&env, CandidateResult { candidate: symbol_short!("Green"), votes: 1, direct: 1, delegated: 0, share_bps: 2500, electorate_bps: 1666 }]);
+    assert_eq!((results.total, results.margin), (4, 1));
+    assert!(results.winners.is_empty());
+
+    env.budget().reset_default();
+    client.vote(&test_data.voters.get(4).unwrap(), &symbol_short!("Laborist"));
+    let results = client.results(&test_data.admin, &0, &10);
+    assert_eq!(results.margin, 0);
+    assert_eq!(results.winners, vec![&env, symbol_short!("Laborist"), symbol_short!("Conserv")]);
+
+    assert_eq!(client.try_results(&test_data.voters.get(5).unwrap(), &0, &10), Err(Ok(Error::NotAuthorized)));
+}
+
+#[test]
+fn delegated_vote_record_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.delegate(&voter0, &voter1);
+    assert_eq!(client.delegated_vote(&voter0), DelegatedVote::NotCast);
+
+    client.vote(&voter1, &symbol_short!("Laborist"));
+    client.vote(&voter2, &symbol_short!("Conserv"));
+
+    assert_eq!(client.vote_record(&voter1), Some(VoteRecord { candidate: symbol_short!("Laborist"), carried: vec![&env, voter0.clone()] }));
+    assert_eq!(client.vote_record(&voter2), Some(VoteRecord { candidate: symbol_short!("Conserv"), carried: Vec::new(&env) }));
+    assert_eq!(client.vote_record(&voter0), None);
+    assert_eq!(client.delegated_vote(&voter0), DelegatedVote::Cast(voter1, symbol_short!("Laborist")));
+    assert_eq!(client.delegated_vote(&voter2), DelegatedVote::NotCast);
+}
+
+#[test]
+fn list_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 5);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+    let voter3 = test_data.voters.get(3).unwrap();
+    let voter4 = test_data.voters.get(4).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.vote(&voter0, &symbol_short!("Laborist"));
+    client.vote(&voter1, &symbol_short!("Conserv"));
+    client.vote(&voter2, &symbol_short!("Green"));
+    client.delegate(&voter3, &voter4);
+
+    assert_eq!(client.list_voters(&0, &2), vec![&env, voter0, voter1]);
+    assert_eq!(client.list_voters(&2, &2), vec![&env, voter2]);
+    assert_eq!(client.list_voters(&5, &2), Vec::new(&env));
+    assert_eq!(client.list_candidates(&1, &10), vec![&env, symbol_short!("Conserv"), symbol_short!("Green")]);
+
+    let expiration = ((ts_end - 1689238844) / 5) as u32 + 60;
+    assert_eq!(client.list_delegations(&0, &10), vec![&env, Delegation { delegator: voter3, delegate: voter4, weight: 1, expiration }]);
+}
+
+#[test]
+fn voter_status_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+    let outsider = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.delegate(&voter0, &voter1);
+    client.vote(&voter1, &symbol_short!("Laborist"));
+
+    let expiration = ((ts_end - 1689238844) / 5) as u32 + 60;
+    assert_eq!(client.voter_status(&voter0), VoterStatus {
+        eligible: Some(true),
+        voted: false,
+        candidate: VoteChoice::NotVoted,
+        delegate: DelegationTarget::Delegate(voter1.clone()),
+        delegators: Vec::new(&env),
+        delegation_expiration: Some(expiration)
+    });
+
+    assert_eq!(client.voter_status(&voter1), VoterStatus {
+        eligible: Some(true),
+        voted: true,
+        candidate: VoteChoice::Candidate(symbol_short!("Laborist")),
+        delegate: DelegationTarget::NotDelegated,
+        delegators: vec![&env, voter0],
+        delegation_expiration: None
+    });
+
+    assert!(!client.voter_status(&voter2).voted);
+    assert_eq!(client.voter_status(&outsider).eligible, Some(false));
+}
+
+#[test]
+fn vote_receipt_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    let receipt0 = client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    let receipt1 = client.vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Conserv"));
+    assert_eq!(receipt0.index, 0);
+    assert_eq!(receipt1.index, 1);
+    assert_eq!(client.votes_root(), receipt1.root);
+
+    let receipt2 = client.vote(&test_data.voters.get(2).unwrap(), &symbol_short!("Conserv"));
+    assert_eq!(client.votes_root(), receipt2.root);
+    let leaves = vec![&env, receipt0.leaf.clone(), receipt1.leaf.clone(), receipt2.leaf.clone()];
+    assert!(client.verify_receipt(&receipt0, &receipt_proof(&env, &leaves, 0)));
+    assert!(client.verify_receipt(&receipt1, &receipt_proof(&env, &leaves, 1)));
+    assert!(client.verify_receipt(&receipt2, &receipt_proof(&env, &leaves, 2)));
+    assert!(!client.verify_receipt(&receipt2, &receipt_proof(&env, &leaves, 1)));
+
+    let forged = Receipt { index: 1, leaf: receipt0.leaf.clone(), root: receipt1.root.clone() };
+    assert!(!client.verify_receipt(&forged, &receipt_proof(&env, &leaves, 1)));
+
+    let forged = Receipt { index: 0, leaf: receipt0.leaf, root: receipt1.root };
+    assert!(!client.verify_receipt(&forged, &receipt_proof(&env, &leaves, 1)));
+}
+
+#[test]
+fn merkle_vote_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 0);
+    let merkle_data = get_merkle_data(&env);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voters = &merkle_data.voters;
+    let proofs = &merkle_data.proofs;
+
+    client.configure(&test_data.admin, &None, &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Merkle(MerkleRoot { root: merkle_data.root.clone(), voters: 4 }));
+
+    client.vote_with_proof(&voters.get(0).unwrap(), &symbol_short!("Laborist"), &1, &proofs.get(0).unwrap());
+    client.delegate_with_proof(&voters.get(2).unwrap(), &3, &proofs.get(2).unwrap(), &voters.get(1).unwrap(), &2, &proofs.get(1).unwrap());
+    client.vote_with_proof(&voters.get(1).unwrap(), &symbol_short!("Conserv"), &2, &proofs.get(1).unwrap());
+
+    let count = client.count(&test_data.admin, &0, &10);
+    assert_eq!(count.get(symbol_short!("Laborist")).unwrap(), 1);
+    assert_eq!(count.get(symbol_short!("Conserv")).unwrap(), 5);
+
+    let stats = client.stats();
+    assert_eq!(stats.holders, 4);
+    assert_eq!(stats.voted, 2);
+    assert_eq!(stats.delegated_votes, 1);
+    assert_eq!(stats.turnout_bps, 7500);
+    assert_eq!(client.voter_status(&voters.get(2).unwrap()).eligible, Some(true));
+    // Leaves are hashed, whether a voter that has not acted is in the tree cannot be told without its proof
+    assert_eq!(client.voter_status(&voters.get(3).unwrap()).eligible, None);
+    assert_eq!(client.voter_status(&Address::generate(&env)).eligible, None);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #10)")]
+fn merkle_vote_with_wrong_weight_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 0);
+    let merkle_data = get_merkle_data(&env);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+
+    client.configure(&test_data.admin, &None, &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Merkle(MerkleRoot { root: merkle_data.root.clone(), voters: 4 }));
+    client.vote_with_proof(&merkle_data.voters.get(0).unwrap(), &symbol_short!("Laborist"), &5, &merkle_data.proofs.get(0).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #8)")]
+fn merkle_vote_requires_proof_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+    let merkle_data = get_merkle_data(&env);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+
+    client.configure(&test_data.admin, &None, &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Merkle(MerkleRoot { root: merkle_data.root, voters: 4 }));
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+}
+
+#[test]
+fn migrate_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    assert_eq!(client.version(), SCHEMA_VERSION);
+    client.vote(&voter0, &symbol_short!("Conserv"));
+    client.delegate(&voter1, &voter2);
+
+    // The first upgradeable layout has nothing to move, migrating only records the version
+    assert_eq!(client.migrate(), SCHEMA_VERSION);
+    assert_eq!(client.version(), SCHEMA_VERSION);
+    assert_eq!(client.list_candidates(&0, &10), vec![&env, symbol_short!("Conserv")]);
+    client.vote(&voter2, &symbol_short!("Laborist"));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 2);
+}
+
+#[test]
+fn upgrade_test() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 0);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &0, &0, &WindowUnit::Timestamp, &Eligibility::Token);
+    assert_eq!(client.version(), SCHEMA_VERSION);
+
+    let wasm_hash = env.deployer().upload_contract_wasm(token::WASM);
+    client.upgrade(&wasm_hash);
+
+    // The ballot address now runs the uploaded code
+    let upgraded = token::Client::new(&env, &client.address);
+    assert_eq!(upgraded.decimals(), 0);
+}
+
+#[test]
+fn ttl_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &20000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.set_retention(&600000);
+    let question = client.add_question(&symbol_short!("budget"), &vec![&env, symbol_short!("yes"), symbol_short!("no")], &Method::Plurality);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    client.vote_questions(&test_data.voters.get(0).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+
+    // Window end (20000) plus the retention period
+    assert_eq!(live_until(&env, &client.address, xdr::ScVal::LedgerKeyContractInstance), 620000);
+
+    env.ledger().with_mut(|l| {l.sequence_number = 29000;});
+    client.bump();
+    client.bump_voters(&0, &10);
+    client.bump_candidates(&0, &10);
+    client.bump_questions(&0, &10);
+
+    let tally_key = xdr::ScVal::try_from(&VRecord::Tally(symbol_short!("Laborist"))).unwrap();
+    let question_key = xdr::ScVal::try_from(&VRecord::QTally(question, symbol_short!("yes"))).unwrap();
+    let vote_key = xdr::ScVal::try_from(&VRecord::Vote(test_data.voters.get(0).unwrap())).unwrap();
+    assert_eq!(live_until(&env, &client.address, xdr::ScVal::LedgerKeyContractInstance), 629000);
+    assert_eq!(live_until(&env, &client.address, tally_key), 629000);
+    assert_eq!(live_until(&env, &client.address, vote_key), 629000);
+    assert_eq!(live_until(&env, &client.address, question_key), 629000);
+}
+
+#[test]
+fn long_window_ttl_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let voter = Address::generate(&env);
+    let provider = MockProviderClient::new(&env, &env.register_contract(None, MockProvider));
+    provider.set_weight(&voter, &1);
+
+    // Windows and retention past what the network keeps are extended as far as allowed
+    client.configure(&admin, &None, &100, &(u32::MAX as u64), &WindowUnit::Ledger, &Eligibility::Provider(provider.address.clone()));
+    client.set_retention(&u32::MAX);
+    client.vote(&voter, &symbol_short!("Laborist"));
+
+    let max_live_until = env.ledger().sequence() + env.as_contract(&client.address, || env.storage().max_ttl()) - 1;
+    let vote_key = xdr::ScVal::try_from(&VRecord::Vote(voter)).unwrap();
+    assert_eq!(live_until(&env, &client.address, xdr::ScVal::LedgerKeyContractInstance), max_live_until);
+    assert_eq!(live_until(&env, &client.address, vote_key), max_live_until);
+
+    // Token ballots lock the voter until the end however long the window runs
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &(u32::MAX as u64), &WindowUnit::Ledger, &Eligibility::Token);
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    assert_eq!(test_data.token.locked_until(&test_data.voters.get(0).unwrap()), u32::MAX);
+}
+
+#[test]
+fn questions_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 4);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+    let voter3 = test_data.voters.get(3).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    let budget = client.add_question(&symbol_short!("budget"), &vec![&env, symbol_short!("yes"), symbol_short!("no")], &Method::Plurality);
+    let board = client.add_question(&symbol_short!("board"), &vec![&env, symbol_short!("alice"), symbol_short!("bob"), symbol_short!("carol")], &Method::Approval);
+    assert_eq!((budget, board), (0, 1));
+    assert_eq!(client.questions().len(), 2);
+
+    client.vote_questions(&voter0, &vec![
+        &env,
+        Answer { question: budget, choices: vec![&env, symbol_short!("no")] },
+        Answer { question: board, choices: vec![&env, symbol_short!("alice"), symbol_short!("carol")] }
+    ]);
+
+    // voter2 carries voter1 weight only for the question it answers
+    client.delegate(&voter1, &voter2);
+    assert_eq!(client.try_vote_questions(&voter2, &Vec::new(&env)), Err(Ok(Error::InvalidOption)));
+    client.vote_questions(&voter2, &vec![&env, Answer { question: budget, choices: vec![&env, symbol_short!("yes")] }]);
+    client.vote_questions(&voter3, &vec![&env, Answer { question: board, choices: vec![&env, symbol_short!("bob")] }]);
+    assert_eq!(client.stats().delegated_votes, 1);
+
+    let budget_count = client.question_count(&budget);
+    assert_eq!(budget_count.get(symbol_short!("yes")).unwrap(), 2);
+    assert_eq!(budget_count.get(symbol_short!("no")).unwrap(), 1);
+
+    let board_count = client.question_count(&board);
+    assert_eq!(board_count.get(symbol_short!("alice")).unwrap(), 1);
+    assert_eq!(board_count.get(symbol_short!("bob")).unwrap(), 1);
+    assert_eq!(board_count.get(symbol_short!("carol")).unwrap(), 1);
+
+    // Answering questions does not consume the candidate vote, the delegator is still counted once
+    env.budget().reset_default();
+    client.vote(&voter0, &symbol_short!("Laborist"));
+    client.vote(&voter2, &symbol_short!("Laborist"));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 3);
+    assert_eq!(client.stats().delegated_votes, 1);
+}
+
+#[test]
+fn schulze_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 5);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let (a, b, c) = (symbol_short!("alice"), symbol_short!("bob"), symbol_short!("carol"));
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    let plain = client.add_question(&symbol_short!("budget"), &vec![&env, symbol_short!("yes"), symbol_short!("no")], &Method::Plurality);
+    let board = client.add_question(&symbol_short!("board"), &vec![&env, a.clone(), b.clone(), c.clone()], &Method::Ranked);
+
+    // voter3 carries voter4 weight, bob gets no first preference yet beats alice head to head
+    client.delegate(&test_data.voters.get(4).unwrap(), &test_data.voters.get(3).unwrap());
+    client.vote_questions(&test_data.voters.get(0).unwrap(), &vec![&env, Answer { question: board, choices: vec![&env, a.clone(), b.clone(), c.clone()] }]);
+    client.vote_questions(&test_data.voters.get(1).unwrap(), &vec![&env, Answer { question: board, choices: vec![&env, a.clone(), b.clone()] }]);
+    client.vote_questions(&test_data.voters.get(2).unwrap(), &vec![&env, Answer { question: board, choices: vec![&env, c.clone(), b.clone(), a.clone()] }]);
+    client.vote_questions(&test_data.voters.get(3).unwrap(), &vec![&env, Answer { question: board, choices: vec![&env, c.clone(), b.clone()] }]);
+
+    let tally = client.ranked_tally(&board);
+    assert_eq!(tally, RankedTally {
+        counts: soroban_sdk::map![&env, (a.clone(), 2), (b.clone(), 0), (c.clone(), 3)],
+        matrix: vec![&env, vec![&env, 0, 2, 2], vec![&env, 3, 0, 2], vec![&env, 3, 3, 0]],
+        paths: vec![&env, vec![&env, 0, 0, 0], vec![&env, 3, 0, 0], vec![&env, 3, 3, 0]],
+        order: vec![&env, c.clone(), b.clone(), a.clone()]
+    });
+
+    assert_eq!(client.try_ranked_tally(&plain), Err(Ok(Error::QuestionNotRanked)));
+    assert_eq!(client.try_ranked_tally(&5), Err(Ok(Error::QuestionNotFound)));
+}
+
+#[test]
+fn question_answers_validation_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter = test_data.voters.get(0).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    let budget = client.add_question(&symbol_short!("budget"), &vec![&env, symbol_short!("yes"), symbol_short!("no")], &Method::Plurality);
+
+    let two_choices = vec![&env, Answer { question: budget, choices: vec![&env, symbol_short!("yes"), symbol_short!("no")] }];
+    assert_eq!(client.try_vote_questions(&voter, &two_choices), Err(Ok(Error::InvalidOption)));
+
+    let unknown_option = vec![&env, Answer { question: budget, choices: vec![&env, symbol_short!("maybe")] }];
+    assert_eq!(client.try_vote_questions(&voter, &unknown_option), Err(Ok(Error::InvalidOption)));
+
+    let unknown_question = vec![&env, Answer { question: 7, choices: vec![&env, symbol_short!("yes")] }];
+    assert_eq!(client.try_vote_questions(&voter, &unknown_question), Err(Ok(Error::QuestionNotFound)));
+
+    let answer = vec![&env, Answer { question: budget, choices: vec![&env, symbol_short!("yes")] }];
+    client.vote_questions(&voter, &answer);
+    assert_eq!(client.try_vote_questions(&voter, &answer), Err(Ok(Error::QuestionAlreadyAnswered)));
+}
+
+#[test]
+fn proposal_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 4);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+    let voter2 = test_data.voters.get(2).unwrap();
+    let voter3 = test_data.voters.get(3).unwrap();
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 2, max_per_address: 1 });
+
+    let id = client.propose(&voter0, &symbol_short!("parking"), &description, &options, &Vec::new(&env));
+    assert_eq!(client.proposal(&id).status, ProposalStatus::Pending);
+    assert_eq!(client.try_propose(&voter0, &symbol_short!("canteen"), &description, &options, &Vec::new(&env)), Err(Ok(Error::ProposalLimitReached)));
+
+    env.budget().reset_default();
+    assert_eq!(client.try_sponsor(&voter0, &id), Err(Ok(Error::AlreadySponsored)));
+    assert_eq!(client.sponsor(&voter1, &id), ProposalStatus::Pending);
+    assert_eq!(client.try_sponsor(&voter1, &id), Err(Ok(Error::AlreadySponsored)));
+    assert_eq!(client.sponsor(&voter2, &id), ProposalStatus::Open);
+    assert_eq!(client.try_sponsor(&voter3, &id), Err(Ok(Error::ProposalNotPending)));
+
+    let proposal = client.proposal(&id);
+    let question = proposal.question.unwrap();
+    assert_eq!(proposal.sponsors, vec![&env, voter1, voter2]);
+    assert_eq!(client.questions().get(question).unwrap().options, options);
+
+    client.vote_questions(&voter3, &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+    assert_eq!(client.question_count(&question).get(symbol_short!("yes")).unwrap(), 1);
+}
+
+#[test]
+fn proposal_requires_token_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let voter = test_data.voters.get(0).unwrap();
+    let outsider = Address::generate(&env);
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    assert_eq!(client.try_propose(&outsider, &symbol_short!("parking"), &description, &options, &Vec::new(&env)), Err(Ok(Error::ProposalsDisabled)));
+
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 1 });
+    assert_eq!(client.try_propose(&outsider, &symbol_short!("parking"), &description, &options, &Vec::new(&env)), Err(Ok(Error::BalanceBelowThreshold)));
+
+    // Proposing pins the snapshot and locks the proposer's token, so it cannot back a second address
+    client.propose(&voter, &symbol_short!("parking"), &description, &options, &Vec::new(&env));
+    assert!(test_data.token.locked_until(&voter) > 0);
+    env.ledger().with_mut(|l| {l.sequence_number += 1;});
+    test_data.token.mint(&outsider);
+    assert_eq!(client.try_propose(&outsider, &symbol_short!("canteen"), &description, &options, &Vec::new(&env)), Err(Ok(Error::BalanceBelowThreshold)));
+
+    // Provider ballots ask the provider instead of the token
+    let provider = MockProviderClient::new(&env, &env.register_contract(None, MockProvider));
+    let client = create_client(&env);
+    client.configure(&test_data.admin, &None, &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Provider(provider.address.clone()));
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 1 });
+    assert_eq!(client.try_propose(&outsider, &symbol_short!("canteen"), &description, &options, &Vec::new(&env)), Err(Ok(Error::VoterNotEligible)));
+    provider.set_weight(&outsider, &1);
+    client.propose(&outsider, &symbol_short!("canteen"), &description, &options, &Vec::new(&env));
+}
+
+#[test]
+fn execute_proposal_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+    let target = TargetClient::new(&env, &env.register_contract(None, Target));
+
+    let ts_start: u64 = 1689238800; // 2023-07-13 09:00:00
+    let ts_end: u64 = 1689551999; // 2023-07-16 23:59:59
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+    let actions = vec![&env, Call { contract: target.address.clone(), function: Symbol::new(&env, "set"), args: vec![&env, 42_u32.into_val(&env)] }];
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &ts_start, &ts_end, &WindowUnit::Timestamp, &Eligibility::Token);
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 2 });
+    let passing = client.propose(&test_data.voters.get(0).unwrap(), &symbol_short!("raise"), &description, &options, &actions);
+    let failing = client.propose(&test_data.voters.get(0).unwrap(), &symbol_short!("cut"), &description, &options, &actions);
+    let passing_question = client.proposal(&passing).question.unwrap();
+    let failing_question = client.proposal(&failing).question.unwrap();
+
+    client.vote_questions(&test_data.voters.get(1).unwrap(), &vec![
+        &env,
+        Answer { question: passing_question, choices: vec![&env, symbol_short!("yes")] },
+        Answer { question: failing_question, choices: vec![&env, symbol_short!("yes")] }
+    ]);
+    client.vote_questions(&test_data.voters.get(2).unwrap(), &vec![
+        &env,
+        Answer { question: passing_question, choices: vec![&env, symbol_short!("yes")] },
+        Answer { question: failing_question, choices: vec![&env, symbol_short!("no")] }
+    ]);
+
+    // Votes are still open
+    assert_eq!(client.try_queue(&passing), Err(Ok(Error::ProposalNotPassed)));
+    assert_eq!(target.value(), 0);
+
+    env.ledger().with_mut(|l| {l.timestamp = ts_end + 1;});
+    assert_eq!(client.try_queue(&failing), Err(Ok(Error::ProposalNotPassed)));
+    assert_eq!(client.try_execute(&failing), Err(Ok(Error::ProposalNotQueued)));
+    client.queue(&passing);
+    assert_eq!(client.execute(&passing).len(), 1);
+    assert_eq!(target.value(), 42);
+    assert_eq!(client.proposal(&passing).status, ProposalStatus::Executed);
+    assert_eq!(client.try_execute(&passing), Err(Ok(Error::ProposalAlreadyExecuted)));
+}
+
+#[test]
+fn timelock_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let target = TargetClient::new(&env, &env.register_contract(None, Target));
+
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+    let actions = vec![&env, Call { contract: target.address.clone(), function: Symbol::new(&env, "set"), args: vec![&env, 7_u32.into_val(&env)] }];
+    let proposer = test_data.voters.get(0).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &200, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.set_timelock(&500);
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 2 });
+    env.ledger().with_mut(|l| {l.sequence_number = 200;});
+    assert_eq!(client.try_set_timelock(&0), Err(Ok(Error::AlreadyConfigured)));
+    let id = client.propose(&proposer, &symbol_short!("raise"), &description, &options, &actions);
+    let canceled = client.propose(&proposer, &symbol_short!("cut"), &description, &options, &actions);
+    let question = client.proposal(&id).question.unwrap();
+    client.vote_questions(&test_data.voters.get(1).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+
+    assert!(client.cancel(&proposer, &canceled));
+    assert_eq!(client.proposal(&canceled).status, ProposalStatus::Canceled);
+    assert_eq!(client.try_cancel(&test_data.voters.get(1).unwrap(), &id), Err(Ok(Error::NotAuthorized)));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1001;});
+    assert_eq!(client.queue(&id), 1501);
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![&env, (client.address.clone(), (symbol_short!("proposal"), symbol_short!("queued")).into_val(&env), (id, 1501_u64).into_val(&env))]
+    );
+    assert_eq!(client.proposal(&id).status, ProposalStatus::Queued);
+    assert_eq!(client.try_execute(&id), Err(Ok(Error::TimelockNotExpired)));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1501;});
+    client.execute(&id);
+    assert_eq!(target.value(), 7);
+    assert_eq!(client.try_cancel(&proposer, &id), Err(Ok(Error::ProposalAlreadyExecuted)));
+}
+
+#[test]
+fn timelock_saturation_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+
+    // A delay too long to ever pass keeps the proposal queued instead of failing the queue call
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &200, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.set_timelock(&u64::MAX);
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 1 });
+    env.ledger().with_mut(|l| {l.sequence_number = 200;});
+    let id = client.propose(&test_data.voters.get(0).unwrap(), &symbol_short!("raise"), &description, &options, &Vec::new(&env));
+    let question = client.proposal(&id).question.unwrap();
+    client.vote_questions(&test_data.voters.get(1).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1001;});
+    assert_eq!(client.queue(&id), u64::MAX);
+    assert_eq!(client.try_execute(&id), Err(Ok(Error::TimelockNotExpired)));
+}
+
+#[test]
+fn guardian_veto_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 99;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let target = TargetClient::new(&env, &env.register_contract(None, Target));
+    let guardian = Address::generate(&env);
+
+    let description = BytesN::from_array(&env, &[1; 32]);
+    let reason = BytesN::from_array(&env, &[9; 32]);
+    let options = vec![&env, symbol_short!("yes"), symbol_short!("no")];
+    let actions = vec![&env, Call { contract: target.address.clone(), function: Symbol::new(&env, "set"), args: vec![&env, 7_u32.into_val(&env)] }];
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.set_guardian(&Guardian { address: guardian, veto_window: 200 });
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    client.set_proposal_rules(&ProposalRules { min_balance: 1, sponsors: 0, max_per_address: 1 });
+    let id = client.propose(&test_data.voters.get(0).unwrap(), &symbol_short!("raise"), &description, &options, &actions);
+    let question = client.proposal(&id).question.unwrap();
+    client.vote_questions(&test_data.voters.get(1).unwrap(), &vec![&env, Answer { question, choices: vec![&env, symbol_short!("yes")] }]);
+
+    // The outcome can only be vetoed once it is final
+    assert_eq!(client.try_veto(&reason), Err(Ok(Error::VetoWindowClosed)));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1001;});
+    assert_eq!(client.queue(&id), 1201);
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1100;});
+    assert!(client.veto(&reason));
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![&env, (client.address.clone(), (symbol_short!("ballot"), symbol_short!("vetoed")).into_val(&env), reason.into_val(&env))]
+    );
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1201;});
+    assert_eq!(client.ballot_status(), BallotStatus::Vetoed);
+    assert_eq!(client.status_reason(), Some(reason));
+    assert_eq!(client.try_execute(&id), Err(Ok(Error::BallotVetoed)));
+    assert_eq!(target.value(), 0);
+}
+
+#[test]
+fn guardian_cancel_ballot_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 99;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let reason = BytesN::from_array(&env, &[9; 32]);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    assert_eq!(client.try_cancel_ballot(&reason), Err(Ok(Error::NoGuardian)));
+
+    // The admin cannot guard itself, nor swap the guardian once voting starts
+    assert_eq!(client.try_set_guardian(&Guardian { address: test_data.admin.clone(), veto_window: 200 }), Err(Ok(Error::InvalidGuardian)));
+    client.set_guardian(&Guardian { address: Address::generate(&env), veto_window: 200 });
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    assert_eq!(client.try_set_guardian(&Guardian { address: Address::generate(&env), veto_window: 200 }), Err(Ok(Error::AlreadyConfigured)));
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    assert!(client.cancel_ballot(&reason));
+    assert_eq!(client.ballot_status(), BallotStatus::Canceled);
+    assert_eq!(client.try_vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Laborist")), Err(Ok(Error::BallotCanceled)));
+}
+
+#[test]
+fn guardian_window_saturation_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 100;});
+    let (vetoed, canceled) = (create_client(&env), create_client(&env));
+    let provider = env.register_contract(None, MockProvider);
+    let reason = BytesN::from_array(&env, &[9; 32]);
+
+    // A veto window reaching past the end of time stays open instead of overflowing
+    for client in [&vetoed, &canceled] {
+        client.configure(&Address::generate(&env), &None, &200, &(u64::MAX - 1), &WindowUnit::Timestamp, &Eligibility::Provider(provider.clone()));
+        client.set_guardian(&Guardian { address: Address::generate(&env), veto_window: u64::MAX });
+    }
+
+    env.ledger().with_mut(|l| {l.timestamp = 300;});
+    assert!(canceled.cancel_ballot(&reason));
+    env.ledger().with_mut(|l| {l.timestamp = u64::MAX;});
+    assert!(vetoed.veto(&reason));
+}
+
+#[test]
+fn pause_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 99;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let guardian = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.set_guardian(&Guardian { address: guardian.clone(), veto_window: 200 });
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    assert_eq!(client.try_pause(&Address::generate(&env)), Err(Ok(Error::NotAuthorized)));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 200;});
+    assert!(client.pause(&guardian));
+    assert!(client.paused());
+    assert_eq!(client.try_pause(&test_data.admin), Err(Ok(Error::Paused)));
+    assert_eq!(client.try_vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist")), Err(Ok(Error::Paused)));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 500;});
+    assert!(client.unpause(&test_data.admin, &true));
+    assert!(!client.paused());
+    assert!(!client.unpause(&test_data.admin, &true));
+
+    // The 300 paused ledgers are added to the voting window
+    env.ledger().with_mut(|l| {l.sequence_number = 1200;});
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    env.ledger().with_mut(|l| {l.sequence_number = 1301;});
+    assert_eq!(client.try_vote(&test_data.voters.get(1).unwrap(), &symbol_short!("Laborist")), Err(Ok(Error::BallotOutOfDate)));
+}
+
+#[test]
+fn pause_extension_keeps_delegation_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 150;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let voter0 = test_data.voters.get(0).unwrap();
+    let voter1 = test_data.voters.get(1).unwrap();
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.delegate(&voter0, &voter1);
+    client.vote(&voter1, &symbol_short!("Laborist"));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 200;});
+    client.pause(&test_data.admin);
+    env.ledger().with_mut(|l| {l.sequence_number = 700;});
+    client.unpause(&test_data.admin, &true);
+
+    // The delegation expired at 1060 but its weight was already cast
+    env.ledger().with_mut(|l| {l.sequence_number = 1100;});
+    assert_eq!(client.try_vote(&voter0, &symbol_short!("Conserv")), Err(Ok(Error::VoterHasHisVoteDelegated)));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 2);
+}
+
+#[test]
+fn transfer_and_revote_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 3);
+    let voter = test_data.voters.get(0).unwrap();
+    let fresh = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.vote(&voter, &symbol_short!("Laborist"));
+    assert_eq!(test_data.token.locked_until(&voter), 1000 + EXTRA_LEDGERS);
+
+    // The token cannot be moved to a fresh address to vote a second time
+    assert_eq!(test_data.token.try_transfer(&voter, &fresh, &1), Err(Ok(soroban_sdk::Error::from_contract_error(8))));
+    assert_eq!(client.try_vote(&fresh, &symbol_short!("Laborist")), Err(Ok(Error::VoterDoesNotHoldToken)));
+    assert_eq!(client.stats().voted, 1);
+
+    env.budget().reset_default();
+    // Delegators are locked as well so their token cannot vote again through another address
+    let delegator = test_data.voters.get(1).unwrap();
+    client.delegate(&delegator, &test_data.voters.get(2).unwrap());
+    assert!(test_data.token.try_transfer(&delegator, &fresh, &1).is_err());
+
+    env.ledger().with_mut(|l| {l.sequence_number = 1000 + EXTRA_LEDGERS + 1;});
+    test_data.token.transfer(&voter, &fresh, &1);
+    assert_eq!(test_data.token.balance(&fresh), 1);
+}
+
+#[test]
+fn snapshot_eligibility_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let voter = test_data.voters.get(0).unwrap();
+    let seller = test_data.voters.get(1).unwrap();
+    let buyer = Address::generate(&env);
+    let late = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &150, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    assert_eq!(client.snapshot(), Some(149));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 200;});
+    test_data.token.mint(&late);
+    test_data.token.transfer(&seller, &buyer, &1);
+
+    // Balances before the opening ledger decide who votes
+    assert_eq!(client.try_vote(&late, &symbol_short!("Laborist")), Err(Ok(Error::VoterDoesNotHoldToken)));
+    assert_eq!(client.try_vote(&buyer, &symbol_short!("Laborist")), Err(Ok(Error::VoterDoesNotHoldToken)));
+    assert_eq!(client.voter_status(&late).eligible, Some(false));
+    client.vote(&seller, &symbol_short!("Laborist"));
+    client.vote(&voter, &symbol_short!("Conserv"));
+    assert_eq!(client.stats().holders, 2);
+}
+
+#[test]
+fn timestamp_snapshot_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.timestamp = 1689238844; l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+    let late = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &1689238800, &1689551999, &WindowUnit::Timestamp, &Eligibility::Token);
+    assert_eq!(client.snapshot(), None);
+
+    // The first vote pins the snapshot for the rest of the ballot
+    client.vote(&test_data.voters.get(0).unwrap(), &symbol_short!("Laborist"));
+    assert_eq!(client.snapshot(), Some(99));
+
+    env.ledger().with_mut(|l| {l.sequence_number = 120;});
+    test_data.token.mint(&late);
+    assert_eq!(client.try_vote(&late, &symbol_short!("Laborist")), Err(Ok(Error::VoterDoesNotHoldToken)));
+}
+
+#[test]
+fn same_ledger_revote_test() {
+    for unit in [WindowUnit::Ledger, WindowUnit::Timestamp] {
+        let env = Env::default();
+        env.ledger().with_mut(|l| {l.timestamp = 1689238844; l.sequence_number = 100;});
+        let client = create_client(&env);
+        let test_data = get_test_data(&env, &client.address, 1);
+        let voter = test_data.voters.get(0).unwrap();
+        let fresh = Address::generate(&env);
+        let (start, end) = match unit {
+            WindowUnit::Ledger => (100, 1000),
+            WindowUnit::Timestamp => (1689238800, 1689551999),
+        };
+
+        client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &start, &end, &unit, &Eligibility::Balance(1));
+        client.vote(&voter, &symbol_short!("Laborist"));
+
+        // Balance ballots do not lock, but a balance received in the ledger votes land in is not part of the snapshot
+        test_data.token.transfer(&voter, &fresh, &1);
+        assert_eq!(client.try_vote(&fresh, &symbol_short!("Laborist")), Err(Ok(Error::VoterDoesNotHoldToken)));
+        assert_eq!(client.stats().voted, 1);
+    }
+}
+
+#[test]
+fn stellar_asset_eligibility_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let asset = env.register_stellar_asset_contract(admin.clone());
+
+    // Standard assets keep no balance history, their holders could vote, transfer and vote again
+    assert_eq!(client.try_configure(&admin, &Some(asset.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidToken)));
+    assert_eq!(client.try_configure(&admin, &Some(asset.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Balance(100)), Err(Ok(Error::InvalidToken)));
+
+    // Provider ballots never read the token, switching them to balances still checks it
+    let provider = env.register_contract(None, MockProvider);
+    client.configure(&admin, &Some(asset.clone()), &200, &1000, &WindowUnit::Ledger, &Eligibility::Provider(provider));
+    assert_eq!(client.try_set_eligibility(&Eligibility::Balance(100)), Err(Ok(Error::InvalidToken)));
+    client.set_eligibility(&Eligibility::Escrow(100));
+}
+
+#[test]
+fn escrow_eligibility_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let asset = env.register_stellar_asset_contract(admin.clone());
+    let asset_admin = soroban_sdk::token::StellarAssetClient::new(&env, &asset);
+    let tk = soroban_sdk::token::Client::new(&env, &asset);
+    let (voter, delegator, fresh) = (Address::generate(&env), Address::generate(&env), Address::generate(&env));
+    asset_admin.mint(&voter, &60);
+    asset_admin.mint(&delegator, &50);
+
+    client.configure(&admin, &Some(asset.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Escrow(50));
+    client.delegate(&delegator, &voter);
+    client.vote(&voter, &symbol_short!("Laborist"));
+    env.budget().reset_default();
+
+    // Voting moves the minimum into the ballot, what is left cannot make a fresh address eligible
+    assert_eq!(client.escrowed(&voter), 50);
+    assert_eq!(client.escrowed(&delegator), 50);
+    assert_eq!(tk.balance(&client.address), 100);
+    tk.transfer(&voter, &fresh, &10);
+    assert_eq!(client.voter_status(&fresh).eligible, Some(false));
+    assert_eq!(client.try_vote(&fresh, &symbol_short!("Conserv")), Err(Ok(Error::VoterDoesNotHoldToken)));
+    assert_eq!(client.voter_status(&voter).eligible, Some(true));
+    assert_eq!(client.count(&admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 2);
+    env.budget().reset_default();
+
+    // Tokens stay in the ballot until nobody can vote anymore
+    assert_eq!(client.try_withdraw(&voter), Err(Ok(Error::EscrowLocked)));
+    env.ledger().with_mut(|l| {l.sequence_number = 1001;});
+    assert_eq!(client.withdraw(&voter), 50);
+    assert_eq!(client.withdraw(&voter), 0);
+    assert_eq!(client.withdraw(&delegator), 50);
+    assert_eq!(tk.balance(&voter), 50);
+    assert_eq!(tk.balance(&delegator), 50);
+    assert_eq!(tk.balance(&client.address), 0);
+}
+
+#[test]
+fn balance_eligibility_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 2);
+    let (voter, delegator) = (test_data.voters.get(0).unwrap(), test_data.voters.get(1).unwrap());
+    let fresh = Address::generate(&env);
+
+    client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Balance(1));
+    client.delegate(&delegator, &voter);
+    client.vote(&voter, &symbol_short!("Laborist"));
+
+    // Balances are read at the snapshot, moving the token after voting gives nothing to the receiver
+    env.ledger().with_mut(|l| {l.sequence_number = 110;});
+    test_data.token.transfer(&voter, &fresh, &1);
+    assert_eq!(client.try_vote(&fresh, &symbol_short!("Conserv")), Err(Ok(Error::VoterDoesNotHoldToken)));
+    assert_eq!(client.voter_status(&fresh).eligible, Some(false));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 2);
+}
+
+#[test]
+fn provider_eligibility_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 0);
+    let provider = MockProviderClient::new(&env, &env.register_contract(None, MockProvider));
+    let (voter, delegator, outsider) = (Address::generate(&env), Address::generate(&env), Address::generate(&env));
+    provider.set_weight(&voter, &3);
+    provider.set_weight(&delegator, &2);
+
+    client.configure(&test_data.admin, &None, &100, &1000, &WindowUnit::Ledger, &Eligibility::Provider(provider.address.clone()));
+    assert_eq!(client.try_vote(&outsider, &symbol_short!("Laborist")), Err(Ok(Error::VoterNotEligible)));
+    assert_eq!(client.voter_status(&voter).eligible, Some(true));
+    assert_eq!(client.voter_status(&outsider).eligible, Some(false));
+
+    // Provider weights apply to votes and delegations alike
+    client.delegate(&delegator, &voter);
+    client.vote(&voter, &symbol_short!("Laborist"));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Laborist")).unwrap(), 5);
+
+    // Weights are capped at u32::MAX and a tally that would overflow rejects the vote
+    let whale = Address::generate(&env);
+    provider.set_weight(&whale, &i128::MAX);
+    assert_eq!(client.try_vote(&whale, &symbol_short!("Laborist")), Err(Ok(Error::TallyOverflow)));
+    client.vote(&whale, &symbol_short!("Conserv"));
+    assert_eq!(client.count(&test_data.admin, &0, &10).get(symbol_short!("Conserv")).unwrap(), u32::MAX);
+    assert_eq!(client.results(&test_data.admin, &0, &10).total, u32::MAX as u64 + 5);
+}
+
+#[test]
+fn configure_validation_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 500;});
+    let client = create_client(&env);
+    let test_data = get_test_data(&env, &client.address, 1);
+    let voter = test_data.voters.get(0).unwrap();
+    let token = test_data.token.address.clone();
+
+    assert_eq!(client.try_vote(&voter, &symbol_short!("Laborist")), Err(Ok(Error::NotConfigured)));
+    assert_eq!(client.try_set_eligibility(&Eligibility::Token), Err(Ok(Error::NotConfigured)));
+    assert_eq!(client.try_stats(), Err(Ok(Error::NotConfigured)));
+
+    assert_eq!(client.try_configure(&test_data.admin, &Some(token.clone()), &1000, &600, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidWindow)));
+    assert_eq!(client.try_configure(&test_data.admin, &Some(token.clone()), &100, &499, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidWindow)));
+    assert_eq!(client.try_configure(&test_data.admin, &Some(token.clone()), &100, &(u32::MAX as u64 + 1), &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidWindow)));
+    assert_eq!(client.try_configure(&test_data.admin, &Some(Address::generate(&env)), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidToken)));
+    assert_eq!(client.try_configure(&test_data.admin, &Some(client.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidToken)));
+    assert_eq!(client.try_configure(&test_data.admin, &None, &100, &1000, &WindowUnit::Ledger, &Eligibility::Balance(1)), Err(Ok(Error::InvalidToken)));
+
+    // Token ballots must be able to lock holders
+    test_data.token.set_locker(&client.address, &false);
+    assert_eq!(client.try_configure(&test_data.admin, &Some(token.clone()), &600, &1000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::InvalidToken)));
+    test_data.token.set_locker(&client.address, &true);
+
+    // The admin may move the window until it opens, nobody else can take the ballot over
+    client.configure(&test_data.admin, &Some(token.clone()), &600, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    assert_eq!(client.try_configure(&voter, &Some(token.clone()), &600, &1000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::AlreadyConfigured)));
+    client.set_eligibility(&Eligibility::Balance(1));
+    client.configure(&test_data.admin, &Some(token.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Token);
+    client.vote(&voter, &symbol_short!("Laborist"));
+
+    // Neither the window nor the electorate moves once voting started
+    assert_eq!(client.try_configure(&test_data.admin, &Some(token.clone()), &100, &2000, &WindowUnit::Ledger, &Eligibility::Token), Err(Ok(Error::AlreadyConfigured)));
+    assert_eq!(client.try_set_eligibility(&Eligibility::Balance(1)), Err(Ok(Error::AlreadyConfigured)));
+}
+
+fn live_until(env: &Env, contract: &Address, key: xdr::ScVal) -> u32 {
+    let contract = xdr::ScAddress::try_from(contract).unwrap();
+    env.to_ledger_snapshot()
+        .ledger_entries
+        .iter()
+        .find_map(|(k, (_, live_until))| match k.as_ref() {
+            xdr::LedgerKey::ContractData(data) if data.contract == contract && data.key == key => *live_until,
+            _ => None
+        })
+        .unwrap()
+}
+
+fn create_client(env: &Env) -> BallotClient<'_> {
     env.mock_all_auths();
 
     let contract_id = env.register_contract(None, Ballot);
-    let client = BallotClient::new(&env, &contract_id);
-    client
-}
\ No newline at end of file
+    BallotClient::new(env, &contract_id)
+}
+// Casts votes numbered from..to, each carrying a delegation and going to a candidate of its own
+fn cast_votes(env: &Env, client: &BallotClient, provider: &MockProviderClient, from: u32, to: u32) -> Vec<Receipt> {
+    let mut receipts = Vec::new(env);
+    for i in from..to {
+        let (voter, delegator) = (Address::generate(env), Address::generate(env));
+        provider.set_weight(&voter, &1);
+        provider.set_weight(&delegator, &1);
+        let name = [b'c', b'a' + (i % 26) as u8, b'a' + (i / 26) as u8];
+
+        env.budget().reset_default();
+        client.delegate(&delegator, &voter);
+        env.budget().reset_default();
+        receipts.push_back(client.vote(&voter, &Symbol::new(env, core::str::from_utf8(&name).unwrap())));
+    }
+
+    receipts
+}
+
+fn cost_of<T>(env: &Env, call: impl FnOnce() -> T) -> (T, u64) {
+    env.budget().reset_default();
+    let result = call();
+    (result, env.budget().cpu_instruction_cost())
+}
+
+// Allows a fifth on top of the small ballot's cost for the deeper receipts branch loaded with the instance,
+// reading every entry instead would multiply it by the twelve times larger ballot
+fn assert_bounded(small_cost: u64, large_cost: u64) {
+    assert!(large_cost * 5 <= small_cost * 6, "{} grew to {}", small_cost, large_cost);
+}
+
+// The test host keeps the whole ledger in one map, so every access gets dearer as entries pile up.
+// Calls are compared between a small and a large ballot at the same point, only work that grows with the votes shows up
+#[test]
+fn bounded_cost_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let small = create_client(&env);
+    let large = create_client(&env);
+    let test_data = get_test_data(&env, &small.address, 0);
+    let provider = MockProviderClient::new(&env, &env.register_contract(None, MockProvider));
+    for client in [&small, &large] {
+        client.configure(&test_data.admin, &Some(test_data.token.address.clone()), &100, &1000, &WindowUnit::Ledger, &Eligibility::Provider(provider.address.clone()));
+    }
+
+    let small_receipts = cast_votes(&env, &small, &provider, 0, 10);
+    let large_receipts = cast_votes(&env, &large, &provider, 0, 120);
+    let (next_small, next_large) = (Address::generate(&env), Address::generate(&env));
+    provider.set_weight(&next_small, &1);
+    provider.set_weight(&next_large, &1);
+
+    let (small_next, small_cost) = cost_of(&env, || small.vote(&next_small, &symbol_short!("Laborist")));
+    let (large_next, large_cost) = cost_of(&env, || large.vote(&next_large, &symbol_short!("Laborist")));
+    assert_bounded(small_cost, large_cost);
+
+    // Proofs have one sibling per level, checking one reads the same entries whatever the votes before and after it
+    let mut small_leaves = Vec::new(&env);
+    let mut large_leaves = Vec::new(&env);
+    small_receipts.iter().for_each(|r| small_leaves.push_back(r.leaf));
+    large_receipts.iter().for_each(|r| large_leaves.push_back(r.leaf));
+    small_leaves.push_back(small_next.leaf);
+    large_leaves.push_back(large_next.leaf);
+    let (small_proof, large_proof) = (receipt_proof(&env, &small_leaves, 0), receipt_proof(&env, &large_leaves, 0));
+    let (valid, small_cost) = cost_of(&env, || small.verify_receipt(&small_receipts.get(0).unwrap(), &small_proof));
+    assert!(valid);
+    let (valid, large_cost) = cost_of(&env, || large.verify_receipt(&large_receipts.get(0).unwrap(), &large_proof));
+    assert!(valid);
+    assert_bounded(small_cost, large_cost);
+
+    // Pages read only their own entries and never more than the page size
+    let (_, small_cost) = cost_of(&env, || small.list_voters(&0, &10));
+    let (_, large_cost) = cost_of(&env, || large.list_voters(&110, &10));
+    assert_bounded(small_cost, large_cost);
+    let (_, small_cost) = cost_of(&env, || small.list_candidates(&0, &10));
+    let (_, large_cost) = cost_of(&env, || large.list_candidates(&110, &10));
+    assert_bounded(small_cost, large_cost);
+    let (_, small_cost) = cost_of(&env, || small.list_delegations(&0, &10));
+    let (_, large_cost) = cost_of(&env, || large.list_delegations(&110, &10));
+    assert_bounded(small_cost, large_cost);
+
+    // Tallies read the requested page of candidates, the total and the margin are kept as votes come in
+    let (small_results, small_cost) = cost_of(&env, || small.results(&test_data.admin, &0, &10));
+    let (large_results, large_cost) = cost_of(&env, || large.results(&test_data.admin, &110, &10));
+    assert_bounded(small_cost, large_cost);
+    assert_eq!((small_results.total, small_results.margin), (21, 0));
+    assert_eq!((large_results.total, large_results.margin, large_results.winners.len()), (241, 0, 10));
+    let (_, small_cost) = cost_of(&env, || small.count(&test_data.admin, &0, &10));
+    let (_, large_cost) = cost_of(&env, || large.count(&test_data.admin, &110, &10));
+    assert_bounded(small_cost, large_cost);
+    let (_, small_cost) = cost_of(&env, || small.bump_candidates(&0, &10));
+    let (_, large_cost) = cost_of(&env, || large.bump_candidates(&110, &10));
+    assert_bounded(small_cost, large_cost);
+
+    assert_eq!(large.list_voters(&0, &u32::MAX).len(), 100);
+    assert_eq!(large.list_candidates(&0, &u32::MAX).len(), 100);
+    assert_eq!(large.list_delegations(&0, &u32::MAX).len(), 100);
+}
@@ -0,0 +1,14 @@
+use soroban_sdk::{contractclient, Address, Env};
+
+pub use soroban_sdk::token::TokenClient;
+
+// Extensions BallotToken offers on top of the standard token interface, used for snapshots and vote locks
+#[allow(dead_code)]
+#[contractclient(name = "BallotTokenClient")]
+pub trait BallotTokenInterface {
+    fn balance_at(env: Env, addr: Address, ledger: u32) -> i128;
+    fn holders_at(env: Env, ledger: u32) -> u32;
+    fn holders(env: Env) -> u32;
+    fn lock(env: Env, locker: Address, addr: Address, until: u32) -> bool;
+    fn is_locker(env: Env, addr: Address) -> bool;
+}
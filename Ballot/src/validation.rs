@@ -1,6 +1,34 @@
 use soroban_sdk::Env;
 
-pub fn is_valid_date(env: &Env, start: &u64, end: &u64) -> bool {
-    let current_timestamp = env.ledger().timestamp();
-    current_timestamp >= *start && current_timestamp <= *end
-}
\ No newline at end of file
+use crate::storage::{Config, WindowUnit};
+
+pub const LEDGER_SECONDS: u64 = 5;
+pub const EXTRA_LEDGERS: u32 = 60; // 5 extra minutes
+
+pub fn current_point(env: &Env, unit: &WindowUnit) -> u64 {
+    match unit {
+        WindowUnit::Timestamp => env.ledger().timestamp(),
+        WindowUnit::Ledger => env.ledger().sequence() as u64
+    }
+}
+
+pub fn is_valid_date(env: &Env, start: &u64, end: &u64, unit: &WindowUnit) -> bool {
+    let current = current_point(env, unit);
+    current >= *start && current <= *end
+}
+
+pub fn remaining_ledgers(env: &Env, cfg: &Config) -> u32 {
+    let remaining = cfg.to.saturating_sub(current_point(env, &cfg.unit));
+    let remaining_ledgers = match cfg.unit {
+        WindowUnit::Timestamp => remaining / LEDGER_SECONDS,
+        WindowUnit::Ledger => remaining
+    };
+
+    remaining_ledgers.min(u32::MAX as u64) as u32
+}
+
+pub fn expiration_ledger(env: &Env, cfg: &Config) -> u32 {
+    env.ledger().sequence()
+        .saturating_add(remaining_ledgers(env, cfg))
+        .saturating_add(EXTRA_LEDGERS)
+}
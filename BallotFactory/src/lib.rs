@@ -0,0 +1,160 @@
+#![no_std]
+
+use soroban_sdk::{contract, contractimpl, contracterror, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, Env, Symbol, Vec};
+
+mod storage;
+
+mod token {
+    soroban_sdk::contractimport!(
+        file = "../BallotToken/target/wasm32-unknown-unknown/release/ballot_token.wasm"
+    );
+}
+
+mod ballot {
+    soroban_sdk::contractimport!(
+        file = "../Ballot/target/wasm32-unknown-unknown/release/ballot_v3.wasm"
+    );
+}
+
+use storage::FRecord;
+pub use storage::{Deployment, WasmHashes};
+
+// Salts are scoped by creator so nobody can squat a pair another address is about to deploy
+fn derive_salt(env: &Env, creator: &Address, salt: &BytesN<32>, kind: Symbol) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(&creator.clone().to_xdr(env));
+    data.append(&Bytes::from_array(env, &salt.to_array()));
+    data.append(&kind.to_xdr(env));
+    env.crypto().sha256(&data)
+}
+
+fn token_salt(env: &Env, creator: &Address, salt: &BytesN<32>) -> BytesN<32> {
+    derive_salt(env, creator, salt, symbol_short!("token"))
+}
+
+fn ballot_salt(env: &Env, creator: &Address, salt: &BytesN<32>) -> BytesN<32> {
+    derive_salt(env, creator, salt, symbol_short!("ballot"))
+}
+
+fn check_initialized(env: &Env) -> Result<(), Error> {
+    if !storage::has_admin(env) {
+        return Err(Error::NotInitialized);
+    }
+
+    Ok(())
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    SaltAlreadyUsed = 3,
+    NotDeployer = 4
+}
+
+#[contract]
+pub struct BallotFactory;
+
+#[contractimpl]
+impl BallotFactory {
+
+    // Tied to the deployer as BallotToken is, otherwise anyone could install their own wasm hashes first
+    pub fn initialize(env: Env, admin: Address, deployer: Address, salt: BytesN<32>, ballot_hash: BytesN<32>, token_hash: BytesN<32>) -> Result<bool, Error> {
+        if storage::has_admin(&env) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        if env.deployer().with_address(deployer.clone(), salt).deployed_address() != env.current_contract_address() {
+            return Err(Error::NotDeployer);
+        }
+
+        deployer.require_auth();
+        storage::store_admin(&env, &admin);
+        storage::store_hashes(&env, &WasmHashes { ballot: ballot_hash, token: token_hash });
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn set_hashes(env: Env, ballot_hash: BytesN<32>, token_hash: BytesN<32>) -> Result<bool, Error> {
+        check_initialized(&env)?;
+        storage::get_admin(&env).require_auth();
+        storage::store_hashes(&env, &WasmHashes { ballot: ballot_hash, token: token_hash });
+        storage::bump_instance(&env);
+        Ok(true)
+    }
+
+    pub fn hashes(env: Env) -> Result<WasmHashes, Error> {
+        check_initialized(&env)?;
+        Ok(storage::get_hashes(&env))
+    }
+
+    pub fn deploy(env: Env, creator: Address, salt: BytesN<32>, start: u64, end: u64, unit: ballot::WindowUnit) -> Result<Deployment, Error> {
+        check_initialized(&env)?;
+        creator.require_auth();
+
+        let ballot_salt = ballot_salt(&env, &creator, &salt);
+        if storage::is_salt_used(&env, &ballot_salt) {
+            return Err(Error::SaltAlreadyUsed);
+        }
+
+        let hashes = storage::get_hashes(&env);
+        let token_salt = token_salt(&env, &creator, &salt);
+        let token_address = env.deployer()
+            .with_current_contract(token_salt.clone())
+            .deploy(hashes.token)
+        ;
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.initialize(&creator, &env.current_contract_address(), &token_salt);
+
+        let ballot_address = env.deployer()
+            .with_current_contract(ballot_salt.clone())
+            .deploy(hashes.ballot)
+        ;
+        token_client.set_locker(&ballot_address, &true);
+        ballot::Client::new(&env, &ballot_address).configure(&creator, &Some(token_address.clone()), &start, &end, &unit, &ballot::Eligibility::Token);
+
+        storage::store_salt(&env, &ballot_salt);
+        let deployment = storage::store_deployment(&env, &creator, &ballot_address, &token_address);
+        storage::bump_instance(&env);
+        env.events().publish((symbol_short!("factory"), symbol_short!("deployed")), deployment.clone());
+        Ok(deployment)
+    }
+
+    pub fn predict(env: Env, creator: Address, salt: BytesN<32>) -> (Address, Address) {
+        let ballot_address = env.deployer().with_current_contract(ballot_salt(&env, &creator, &salt)).deployed_address();
+        let token_address = env.deployer().with_current_contract(token_salt(&env, &creator, &salt)).deployed_address();
+        (ballot_address, token_address)
+    }
+
+    // Keeps a page of deployment records and their creators' lists alive
+    pub fn bump_deployments(env: Env, offset: u32, limit: u32) {
+        storage::bump_instance(&env);
+        let total = storage::get_deployments_count(&env);
+        let end = offset.saturating_add(limit.min(storage::MAX_PAGE_SIZE)).min(total);
+        for id in offset.min(end)..end {
+            let deployment = storage::get_deployment(&env, id).unwrap();
+            storage::bump_record(&env, &FRecord::Deployment(id));
+            storage::bump_record(&env, &FRecord::Creator(deployment.creator));
+        }
+    }
+
+    pub fn deployment(env: Env, id: u32) -> Option<Deployment> {
+        storage::get_deployment(&env, id)
+    }
+
+    pub fn deployments(env: Env, offset: u32, limit: u32) -> Vec<Deployment> {
+        storage::get_deployments(&env, offset, limit)
+    }
+
+    pub fn created_by(env: Env, creator: Address) -> Vec<u32> {
+        storage::get_creator_deployments(&env, &creator)
+    }
+
+    pub fn count(env: Env) -> u32 {
+        storage::get_deployments_count(&env)
+    }
+}
+
+mod test;
@@ -0,0 +1,116 @@
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, Symbol, Vec};
+
+pub const ADMIN: Symbol = symbol_short!("admin");
+pub const HASHES: Symbol = symbol_short!("hashes");
+pub const COUNT: Symbol = symbol_short!("count");
+
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const INSTANCE_TTL: u32 = 30 * DAY_IN_LEDGERS;
+pub const RECORD_TTL: u32 = 90 * DAY_IN_LEDGERS;
+
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WasmHashes {
+    pub ballot: BytesN<32>,
+    pub token: BytesN<32>
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Deployment {
+    pub id: u32,
+    pub creator: Address,
+    pub ballot: Address,
+    pub token: Address
+}
+
+#[contracttype]
+pub enum FRecord {
+    Deployment(u32),
+    Creator(Address),
+    Salt(BytesN<32>)
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&ADMIN)
+}
+
+pub fn get_admin(env: &Env) -> Address {
+    env.storage().instance().get(&ADMIN).unwrap()
+}
+
+pub fn store_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&ADMIN, admin);
+}
+
+pub fn get_hashes(env: &Env) -> WasmHashes {
+    env.storage().instance().get(&HASHES).unwrap()
+}
+
+pub fn store_hashes(env: &Env, hashes: &WasmHashes) {
+    env.storage().instance().set(&HASHES, hashes);
+}
+
+pub fn bump_instance(env: &Env) {
+    env.storage().instance().extend_ttl(INSTANCE_TTL, INSTANCE_TTL);
+}
+
+pub fn bump_record(env: &Env, key: &FRecord) {
+    if env.storage().persistent().has(key) {
+        env.storage().persistent().extend_ttl(key, RECORD_TTL, RECORD_TTL);
+    }
+}
+
+pub fn get_deployments_count(env: &Env) -> u32 {
+    env.storage().instance().get(&COUNT).unwrap_or(0)
+}
+
+pub fn get_deployment(env: &Env, id: u32) -> Option<Deployment> {
+    env.storage().persistent().get(&FRecord::Deployment(id))
+}
+
+pub fn get_creator_deployments(env: &Env, creator: &Address) -> Vec<u32> {
+    env.storage().persistent().get(&FRecord::Creator(creator.clone())).unwrap_or(Vec::new(env))
+}
+
+pub fn is_salt_used(env: &Env, salt: &BytesN<32>) -> bool {
+    env.storage().persistent().has(&FRecord::Salt(salt.clone()))
+}
+
+pub fn store_salt(env: &Env, salt: &BytesN<32>) {
+    let key = FRecord::Salt(salt.clone());
+    env.storage().persistent().set(&key, &true);
+    env.storage().persistent().extend_ttl(&key, RECORD_TTL, RECORD_TTL);
+}
+
+pub fn store_deployment(env: &Env, creator: &Address, ballot: &Address, token: &Address) -> Deployment {
+    let id = get_deployments_count(env);
+    let deployment = Deployment { id, creator: creator.clone(), ballot: ballot.clone(), token: token.clone() };
+    let key = FRecord::Deployment(id);
+    env.storage().persistent().set(&key, &deployment);
+    env.storage().persistent().extend_ttl(&key, RECORD_TTL, RECORD_TTL);
+
+    let mut ids = get_creator_deployments(env, creator);
+    ids.push_back(id);
+    let key = FRecord::Creator(creator.clone());
+    env.storage().persistent().set(&key, &ids);
+    env.storage().persistent().extend_ttl(&key, RECORD_TTL, RECORD_TTL);
+
+    env.storage().instance().set(&COUNT, &(id + 1));
+    deployment
+}
+
+pub fn get_deployments(env: &Env, offset: u32, limit: u32) -> Vec<Deployment> {
+    let mut deployments = Vec::new(env);
+    let count = get_deployments_count(env);
+    let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(count);
+    for id in offset..end {
+        if let Some(d) = get_deployment(env, id) {
+            deployments.push_back(d);
+        }
+    }
+
+    deployments
+}
@@ -0,0 +1,100 @@
+#![cfg(test)]
+
+use soroban_sdk::{symbol_short, testutils::{Address as _, Ledger}, xdr, Address, BytesN, Env};
+
+use super::{ballot, storage::{FRecord, RECORD_TTL}, token, BallotFactory, BallotFactoryClient, Error};
+
+fn create_client(env: &Env) -> BallotFactoryClient<'_> {
+    env.mock_all_auths();
+
+    let deployer = Address::generate(env);
+    let salt = BytesN::from_array(env, &[0; 32]);
+    let contract_id = env.deployer().with_address(deployer.clone(), salt.clone()).deployed_address();
+    env.register_contract(&contract_id, BallotFactory);
+    let client = BallotFactoryClient::new(env, &contract_id);
+    let ballot_hash = env.deployer().upload_contract_wasm(ballot::WASM);
+    let token_hash = env.deployer().upload_contract_wasm(token::WASM);
+    client.initialize(&Address::generate(env), &deployer, &salt, &ballot_hash, &token_hash);
+
+    // The test env shares one budget across calls while on chain each transaction gets its own
+    env.budget().reset_default();
+    client
+}
+
+#[test]
+fn initialize_test() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let hash = BytesN::from_array(&env, &[0; 32]);
+
+    assert_eq!(client.try_initialize(&Address::generate(&env), &Address::generate(&env), &hash, &hash, &hash), Err(Ok(Error::AlreadyInitialized)));
+
+    // Somebody watching the deployment cannot initialize the factory before its deployer
+    let contract_id = env.register_contract(None, BallotFactory);
+    let uninitialized = BallotFactoryClient::new(&env, &contract_id);
+    assert_eq!(uninitialized.try_initialize(&Address::generate(&env), &Address::generate(&env), &hash, &hash, &hash), Err(Ok(Error::NotDeployer)));
+    assert_eq!(uninitialized.try_deploy(&Address::generate(&env), &hash, &0, &0, &ballot::WindowUnit::Ledger), Err(Ok(Error::NotInitialized)));
+}
+
+#[test]
+fn deploy_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let creator = Address::generate(&env);
+    let voter = Address::generate(&env);
+    let salt = BytesN::from_array(&env, &[1; 32]);
+
+    let (ballot_address, token_address) = client.predict(&creator, &salt);
+    let deployment = client.deploy(&creator, &salt, &101, &1000, &ballot::WindowUnit::Ledger);
+    env.budget().reset_default();
+    assert_eq!(deployment.id, 0);
+    assert_eq!(deployment.creator, creator);
+    assert_eq!(deployment.ballot, ballot_address);
+    assert_eq!(deployment.token, token_address);
+
+    // The pair is wired: the creator administers the token and voters holding it can vote
+    let token_client = token::Client::new(&env, &deployment.token);
+    token_client.mint(&voter);
+    env.budget().reset_default();
+    let ballot_client = ballot::Client::new(&env, &deployment.ballot);
+    env.ledger().with_mut(|l| {l.sequence_number = 101;});
+    ballot_client.vote(&voter, &symbol_short!("Laborist"));
+    assert_eq!(ballot_client.voter_status(&voter).candidate, ballot::VoteChoice::Candidate(symbol_short!("Laborist")));
+    assert!(token_client.locked_until(&voter) > 0);
+
+    // Same salt from the same creator collides, another creator gets its own pair
+    assert_eq!(client.try_deploy(&creator, &salt, &100, &1000, &ballot::WindowUnit::Ledger), Err(Ok(Error::SaltAlreadyUsed)));
+    env.budget().reset_default();
+    let other = Address::generate(&env);
+    let second = client.deploy(&other, &salt, &100, &1000, &ballot::WindowUnit::Ledger);
+    env.budget().reset_default();
+    assert_ne!(second.ballot, deployment.ballot);
+
+    assert_eq!(client.count(), 2);
+    assert_eq!(client.deployment(&1), Some(second.clone()));
+    assert_eq!(client.deployments(&0, &10).len(), 2);
+    assert_eq!(client.created_by(&creator), soroban_sdk::vec![&env, 0]);
+    assert_eq!(client.created_by(&other), soroban_sdk::vec![&env, 1]);
+
+    // Records outlive the instance's own bumps only if somebody keeps them alive
+    let deployment_key = xdr::ScVal::try_from(&FRecord::Deployment(0)).unwrap();
+    let creator_key = xdr::ScVal::try_from(&FRecord::Creator(creator)).unwrap();
+    assert_eq!(live_until(&env, &client.address, deployment_key.clone()), 100 + RECORD_TTL);
+    env.ledger().with_mut(|l| {l.sequence_number = 5000;});
+    client.bump_deployments(&0, &10);
+    assert_eq!(live_until(&env, &client.address, deployment_key), 5000 + RECORD_TTL);
+    assert_eq!(live_until(&env, &client.address, creator_key), 5000 + RECORD_TTL);
+}
+
+fn live_until(env: &Env, contract: &Address, key: xdr::ScVal) -> u32 {
+    let contract = xdr::ScAddress::try_from(contract).unwrap();
+    env.to_ledger_snapshot()
+        .ledger_entries
+        .iter()
+        .find_map(|(k, (_, live_until))| match k.as_ref() {
+            xdr::LedgerKey::ContractData(data) if data.contract == contract && data.key == key => *live_until,
+            _ => None
+        })
+        .unwrap()
+}
@@ -0,0 +1,83 @@
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+
+// A checkpoint holds the value a counter takes from `ledger` onwards
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Checkpoint {
+    pub ledger: u32,
+    pub value: i128
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum Series {
+    Balance(Address),
+    Holders
+}
+
+fn get_count(e: &Env, series: &Series) -> u32 {
+    e.storage().persistent().get(&DataKey::CheckpointCount(series.clone())).unwrap_or(0)
+}
+
+fn get_checkpoint(e: &Env, series: &Series, index: u32) -> Checkpoint {
+    e.storage().persistent().get(&DataKey::Checkpoint(series.clone(), index)).unwrap()
+}
+
+fn set_persistent<V: soroban_sdk::IntoVal<Env, soroban_sdk::Val>>(e: &Env, key: &DataKey, value: &V) {
+    e.storage().persistent().set(key, value);
+    e.storage()
+        .persistent()
+        .extend_ttl(key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+fn set_checkpoint(e: &Env, series: &Series, index: u32, checkpoint: &Checkpoint) {
+    set_persistent(e, &DataKey::Checkpoint(series.clone(), index), checkpoint);
+}
+
+fn set_count(e: &Env, series: &Series, count: u32) {
+    set_persistent(e, &DataKey::CheckpointCount(series.clone()), &count);
+}
+
+// Every change of a series goes through here, so a ledger before its first checkpoint saw a zero value
+pub fn record(e: &Env, series: Series, value: i128) {
+    let ledger = e.ledger().sequence();
+    let mut count = get_count(e, &series);
+    if count > 0 && get_checkpoint(e, &series, count - 1).ledger == ledger {
+        set_checkpoint(e, &series, count - 1, &Checkpoint { ledger, value });
+    } else {
+        set_checkpoint(e, &series, count, &Checkpoint { ledger, value });
+        count += 1;
+    }
+
+    set_count(e, &series, count);
+}
+
+// Index of the first checkpoint recorded after ledger
+fn search(count: u32, ledger: u32, get: impl Fn(u32) -> Checkpoint) -> u32 {
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let mid = (low + high) / 2;
+        if get(mid).ledger <= ledger {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
+
+// Returns None when nothing has been recorded for series, callers fall back to the current value
+pub fn value_at(e: &Env, series: &Series, ledger: u32) -> Option<i128> {
+    let count = get_count(e, series);
+    if count == 0 {
+        return None;
+    }
+
+    match search(count, ledger, |i| get_checkpoint(e, series, i)) {
+        0 => Some(0),
+        i => Some(get_checkpoint(e, series, i - 1).value)
+    }
+}
@@ -1,7 +1,22 @@
 #![no_std]
 
-use soroban_sdk::{contract, contracttype, contractimpl, contracterror, symbol_short, Address, Env, Symbol};
+use soroban_sdk::{contract, contracttype, contractimpl, contracterror, panic_with_error, symbol_short, token::TokenInterface, Address, BytesN, Env, String, Symbol};
+
+mod checkpoints;
+
+use checkpoints::Series;
 pub const TOKEN_ADMIN: Symbol = symbol_short!("t_admin");
+pub const HOLDERS: Symbol = symbol_short!("holders");
+pub const VERSION: Symbol = symbol_short!("version");
+pub const GUARDIAN: Symbol = symbol_short!("t_guard");
+pub const PAUSED: Symbol = symbol_short!("paused");
+
+// Tokens deployed before the upgrade entrypoint existed cannot run new code, versions count from the first upgradeable layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const DECIMALS: u32 = 0;
+pub const NAME: &str = "Ballot Token";
+pub const SYMBOL: &str = "BALLOT";
 
 pub const DAY_IN_LEDGERS: u32 = 17280;
 pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
@@ -18,25 +33,92 @@ pub enum Error {
     AddressAlreadyHoldsToken = 2,
     AddressDoesNotHoldToken = 3,
     AddressAlreadyHasAllowance = 4,
-    ExpirationLedgerLessThanCurrentLedger = 5
+    ExpirationLedgerLessThanCurrentLedger = 5,
+    Paused = 6,
+    NotAuthorized = 7,
+    TokenLocked = 8,
+    InsufficientBalance = 9,
+    InsufficientAllowance = 10,
+    NegativeAmount = 11,
+    NotInitialized = 12,
+    NotDeployer = 13
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct AllowanceDataKey {
+    pub from: Address,
+    pub spender: Address
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32
 }
 
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
     Balance(Address),
-    Allowance(Address),
-    Blocking(Address)
+    Allowance(AllowanceDataKey),
+    Delegated(Address),
+    Delegating(Address),
+    Locker(Address),
+    Lock(Address),
+    Checkpoint(Series, u32),
+    CheckpointCount(Series)
 }
 
 fn has_admin(e: &Env) -> bool {
-    let has_admin = e.storage().instance().has(&TOKEN_ADMIN);
-    has_admin
+    e.storage().instance().has(&TOKEN_ADMIN)
 }
 
-fn get_balance(e: &Env, addr: Address)-> u32 {
+fn get_admin(e: &Env) -> Result<Address, Error> {
+    e.storage().instance().get(&TOKEN_ADMIN).ok_or(Error::NotInitialized)
+}
+
+fn bump_instance(e: &Env) {
+    e.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn is_paused(e: &Env) -> bool {
+    e.storage().instance().get(&PAUSED).unwrap_or(false)
+}
+
+fn check_pauser(e: &Env, caller: &Address) -> Result<(), Error> {
+    caller.require_auth();
+    let guardian: Option<Address> = e.storage().instance().get(&GUARDIAN);
+    if *caller != get_admin(e)? && Some(caller.clone()) != guardian {
+        return Err(Error::NotAuthorized);
+    }
+
+    Ok(())
+}
+
+fn check_amount(e: &Env, amount: i128) {
+    if amount < 0 {
+        panic_with_error!(e, Error::NegativeAmount);
+    }
+}
+
+// Balances and allowances can only move while the token is running and the holder is not locked by a ballot
+fn check_movable(e: &Env, from: &Address) {
+    if is_paused(e) {
+        panic_with_error!(e, Error::Paused);
+    }
+
+    if get_lock(e, from.clone()) > 0 {
+        panic_with_error!(e, Error::TokenLocked);
+    }
+}
+
+fn get_balance(e: &Env, addr: Address) -> i128 {
     let key = DataKey::Balance(addr);
-    if let Some(b) = e.storage().persistent().get::<DataKey, u32>(&key) {
+    if let Some(b) = e.storage().persistent().get::<DataKey, i128>(&key) {
         e.storage()
             .persistent()
             .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
@@ -47,24 +129,101 @@ fn get_balance(e: &Env, addr: Address)-> u32 {
     0
 }
 
-fn get_allowance(e: &Env, addr: Address) -> bool {
-    let allowance_key = DataKey::Allowance(addr);
-    if let Some(_a) = e.storage().temporary().get::<_, Address>(&allowance_key) {
-        return true;
+// Keeps the holders count and balance checkpoints in step with every balance change
+fn set_balance(e: &Env, addr: Address, amount: i128) {
+    let previous = get_balance(e, addr.clone());
+    let key = DataKey::Balance(addr.clone());
+    if amount == 0 {
+        e.storage().persistent().remove(&key);
+    } else {
+        e.storage().persistent().set(&key, &amount);
+        e.storage()
+            .persistent()
+            .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    }
+
+    checkpoints::record(e, Series::Balance(addr), amount);
+    match (previous > 0, amount > 0) {
+        (false, true) => set_holders(e, get_holders(e) + 1),
+        (true, false) => set_holders(e, get_holders(e) - 1),
+        _ => ()
+    }
+}
+
+fn spend_balance(e: &Env, addr: Address, amount: i128) {
+    let balance = get_balance(e, addr.clone());
+    if balance < amount {
+        panic_with_error!(e, Error::InsufficientBalance);
     }
 
-    false
+    set_balance(e, addr, balance - amount);
+}
+
+fn receive_balance(e: &Env, addr: Address, amount: i128) {
+    let balance = get_balance(e, addr.clone());
+    set_balance(e, addr, balance + amount);
+}
+
+fn get_holders(e: &Env) -> u32 {
+    e.storage().instance().get(&HOLDERS).unwrap_or(0)
+}
+
+fn set_holders(e: &Env, holders: u32) {
+    checkpoints::record(e, Series::Holders, holders as i128);
+    e.storage().instance().set(&HOLDERS, &holders);
 }
 
-fn get_blocking(e: &Env, addr: Address) -> bool {
-    let blocking_key = DataKey::Blocking(addr);
-    if let Some(_b) = e.storage().temporary().get::<_, Address>(&blocking_key) {
-        return true;
+fn get_allowance(e: &Env, from: Address, spender: Address) -> AllowanceValue {
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    match e.storage().temporary().get::<_, AllowanceValue>(&key) {
+        Some(a) if a.expiration_ledger >= e.ledger().sequence() => a,
+        _ => AllowanceValue { amount: 0, expiration_ledger: 0 }
     }
+}
 
-    false
+fn set_allowance(e: &Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+    if amount > 0 && expiration_ledger < e.ledger().sequence() {
+        panic_with_error!(e, Error::ExpirationLedgerLessThanCurrentLedger);
+    }
+
+    let key = DataKey::Allowance(AllowanceDataKey { from, spender });
+    e.storage().temporary().set(&key, &AllowanceValue { amount, expiration_ledger });
+    if amount > 0 {
+        let live_for = expiration_ledger - e.ledger().sequence();
+        e.storage().temporary().extend_ttl(&key, live_for, live_for);
+    }
 }
 
+fn spend_allowance(e: &Env, from: Address, spender: Address, amount: i128) {
+    let allowance = get_allowance(e, from.clone(), spender.clone());
+    if allowance.amount < amount {
+        panic_with_error!(e, Error::InsufficientAllowance);
+    }
+
+    if amount > 0 {
+        set_allowance(e, from, spender, allowance.amount - amount, allowance.expiration_ledger);
+    }
+}
+
+fn has_delegated_votes(e: &Env, addr: Address) -> bool {
+    e.storage().temporary().has(&DataKey::Delegated(addr))
+}
+
+fn is_delegating(e: &Env, addr: Address) -> bool {
+    e.storage().temporary().has(&DataKey::Delegating(addr))
+}
+
+fn is_locker(e: &Env, addr: Address) -> bool {
+    e.storage().instance().get(&DataKey::Locker(addr)).unwrap_or(false)
+}
+
+fn get_lock(e: &Env, addr: Address) -> u32 {
+    let lock_key = DataKey::Lock(addr);
+    match e.storage().persistent().get::<_, u32>(&lock_key) {
+        Some(until) if until >= e.ledger().sequence() => until,
+        _ => 0
+    }
+}
 
 #[contract]
 pub struct BallotToken;
@@ -72,84 +231,173 @@ pub struct BallotToken;
 #[contractimpl]
 impl BallotToken {
 
-    pub fn initialize(e: Env, admin: Address) -> Result<bool, Error> {
+    // Only whoever deployed the token with salt can initialize it, so the call cannot be front-run. Soroban has no
+    // constructors yet, the factory and the eligibility providers are initialized the same way
+    pub fn initialize(e: Env, admin: Address, deployer: Address, salt: BytesN<32>) -> Result<bool, Error> {
 
         if has_admin(&e) {
             return Err(Error::TokenAlreadyInitialized);
         }
 
+        if e.deployer().with_address(deployer.clone(), salt).deployed_address() != e.current_contract_address() {
+            return Err(Error::NotDeployer);
+        }
+
+        deployer.require_auth();
         e.storage().instance().set(&TOKEN_ADMIN, &admin);
+        e.storage().instance().set(&VERSION, &SCHEMA_VERSION);
         Ok(true)
         
     }
 
-    pub fn mint(e: Env, addr: Address) -> Result<u32, Error> {
-        
-        let admin: Address = e.storage().instance().get(&TOKEN_ADMIN).unwrap();
-        admin.require_auth();
+    pub fn upgrade(e: Env, new_wasm_hash: BytesN<32>) -> Result<bool, Error> {
+        get_admin(&e)?.require_auth();
+        e.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(true)
+    }
 
-        if get_balance(&e, addr.clone()) > 0 {
-            return Err(Error::AddressAlreadyHoldsToken);
+    pub fn set_guardian(e: Env, guardian: Address) -> Result<bool, Error> {
+        get_admin(&e)?.require_auth();
+        e.storage().instance().set(&GUARDIAN, &guardian);
+        Ok(true)
+    }
+
+    pub fn set_locker(e: Env, locker: Address, enabled: bool) -> Result<bool, Error> {
+        get_admin(&e)?.require_auth();
+
+        if enabled {
+            e.storage().instance().set(&DataKey::Locker(locker), &true);
+        } else {
+            e.storage().instance().remove(&DataKey::Locker(locker));
         }
 
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        Ok(true)
+    }
 
-        let key = DataKey::Balance(addr.clone());
-        let amount: u32 = 1;
-        e.storage().persistent().set(&key, &amount);
-        Ok(amount)
+    pub fn is_locker(e: Env, addr: Address) -> bool {
+        is_locker(&e, addr)
     }
 
-    pub fn balance(e: Env, addr: Address) -> u32 {
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    // Lockers (ballots) freeze a holder's token until the given ledger so it cannot be moved to vote twice
+    pub fn lock(e: Env, locker: Address, addr: Address, until: u32) -> Result<bool, Error> {
+        locker.require_auth();
+        if !is_locker(&e, locker) {
+            return Err(Error::NotAuthorized);
+        }
+
+        if until < e.ledger().sequence() {
+            return Err(Error::ExpirationLedgerLessThanCurrentLedger);
+        }
 
-        let b: u32 = get_balance(&e, addr);
-        b
+        // Nothing to freeze, e.g. a holder who moved its token after a snapshot
+        if get_balance(&e, addr.clone()) < 1 {
+            return Ok(false);
+        }
+
+        // Ballots may run longer than any entry can live, a persistent lock is archived with its value instead of vanishing early
+        let until = until.max(get_lock(&e, addr.clone()));
+        let lock_key = DataKey::Lock(addr);
+        let live_for = (until - e.ledger().sequence()).min(e.storage().max_ttl());
+        e.storage().persistent().set(&lock_key, &until);
+        e.storage().persistent().extend_ttl(&lock_key, live_for, live_for);
+
+        Ok(true)
     }
 
-    pub fn transfer(e: Env, from: Address, to: Address) -> Result<bool, Error> {
-        from.require_auth();
+    pub fn locked_until(e: Env, addr: Address) -> u32 {
+        get_lock(&e, addr)
+    }
 
-        if get_balance(&e, from.clone()) == 0 {
-            return Err(Error::AddressDoesNotHoldToken);
-        }
+    pub fn pause(e: Env, caller: Address) -> Result<bool, Error> {
+        check_pauser(&e, &caller)?;
+        e.storage().instance().set(&PAUSED, &true);
+        e.events().publish((symbol_short!("token"), symbol_short!("paused")), caller);
+        Ok(true)
+    }
+
+    pub fn unpause(e: Env, caller: Address) -> Result<bool, Error> {
+        check_pauser(&e, &caller)?;
+        e.storage().instance().remove(&PAUSED);
+        e.events().publish((symbol_short!("token"), symbol_short!("unpaused")), caller);
+        Ok(true)
+    }
+
+    pub fn paused(e: Env) -> bool {
+        is_paused(&e)
+    }
+
+    // Each step moves the storage one version forward, the first upgradeable layout has none yet
+    pub fn migrate(e: Env) -> Result<u32, Error> {
+        get_admin(&e)?.require_auth();
+        e.storage().instance().set(&VERSION, &SCHEMA_VERSION);
+        bump_instance(&e);
+        Ok(SCHEMA_VERSION)
+    }
+
+    // Initializing writes the version, a token without one holds nothing to migrate
+    pub fn version(e: Env) -> u32 {
+        e.storage().instance().get(&VERSION).unwrap_or(SCHEMA_VERSION)
+    }
+
+    // Each address is issued a single voting token
+    pub fn mint(e: Env, addr: Address) -> Result<i128, Error> {
+        let admin = get_admin(&e)?;
+        admin.require_auth();
 
-        if get_balance(&e, to.clone()) > 0 {
+        if get_balance(&e, addr.clone()) > 0 {
             return Err(Error::AddressAlreadyHoldsToken);
         }
 
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
-
-        let from_key = DataKey::Balance(from.clone());
-        let to_key   = DataKey::Balance(to.clone());
-        let amount: u32 = 1;
+        bump_instance(&e);
+        let amount: i128 = 1;
+        set_balance(&e, addr.clone(), amount);
+        e.events().publish((symbol_short!("mint"), admin, addr), amount);
+        Ok(amount)
+    }
 
-        e.storage().persistent().remove(&from_key); // from toklen is burned
-        e.storage().persistent().set(&to_key, &amount);
-        e.storage()
-            .persistent()
-            .extend_ttl(&to_key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    pub fn clawback(e: Env, from: Address, amount: i128) -> Result<bool, Error> {
+        check_amount(&e, amount);
+        let admin = get_admin(&e)?;
+        admin.require_auth();
 
+        bump_instance(&e);
+        spend_balance(&e, from.clone(), amount);
+        e.events().publish((symbol_short!("clawback"), admin, from), amount);
         Ok(true)
     }
 
-    pub fn approve(e: Env, from: Address, spender: Address, expiration: u32) -> Result<bool, Error> {
+    pub fn balance_at(e: Env, addr: Address, ledger: u32) -> i128 {
+        checkpoints::value_at(&e, &Series::Balance(addr.clone()), ledger)
+            .unwrap_or_else(|| get_balance(&e, addr))
+    }
+
+    pub fn holders_at(e: Env, ledger: u32) -> u32 {
+        checkpoints::value_at(&e, &Series::Holders, ledger)
+            .map(|h| h as u32)
+            .unwrap_or_else(|| get_holders(&e))
+    }
+
+    pub fn holders(e: Env) -> u32 {
+        bump_instance(&e);
+        get_holders(&e)
+    }
+
+    // Lends the vote of from to a holder until expiration, a holder can only lend or borrow one vote at a time
+    pub fn delegate_vote(e: Env, from: Address, to: Address, expiration: u32) -> Result<bool, Error> {
         from.require_auth();
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
         if expiration < e.ledger().sequence(){
             return Err(Error::ExpirationLedgerLessThanCurrentLedger);
         }
 
-        if get_blocking(&e, from.clone()) {
+        if is_delegating(&e, from.clone()) {
             return Err(Error::AddressAlreadyHasAllowance);
         }
 
-        if get_allowance(&e, spender.clone()) {
+        if has_delegated_votes(&e, to.clone()) {
             return Err(Error::AddressAlreadyHasAllowance);
         }
 
@@ -157,58 +405,117 @@ impl BallotToken {
             return Err(Error::AddressDoesNotHoldToken);
         }
 
-        if get_balance(&e, spender.clone()) < 1 {
+        if get_balance(&e, to.clone()) < 1 {
             return Err(Error::AddressDoesNotHoldToken);
         }
 
-        let allowance_key = DataKey::Allowance(spender.clone());
-        let blocking_key  = DataKey::Blocking(from.clone());
-        e.storage().temporary().set(&allowance_key, &from);
-        e.storage().temporary().set(&blocking_key, &spender);
+        let delegated_key = DataKey::Delegated(to.clone());
+        let delegating_key  = DataKey::Delegating(from.clone());
+        e.storage().temporary().set(&delegated_key, &from);
+        e.storage().temporary().set(&delegating_key, &to);
 
         let live_for = expiration
             .checked_sub(e.ledger().sequence())
             .unwrap()
         ;
 
-        e.storage().temporary().extend_ttl(&allowance_key, live_for, live_for);
-        e.storage().temporary().extend_ttl(&blocking_key, live_for, live_for);
+        e.storage().temporary().extend_ttl(&delegated_key, live_for, live_for);
+        e.storage().temporary().extend_ttl(&delegating_key, live_for, live_for);
 
         Ok(true)
     }
 
-    pub fn allowance(e: &Env, addr: Address) -> bool {
+    pub fn has_delegated_votes(e: Env, addr: Address) -> bool {
+        bump_instance(&e);
+        has_delegated_votes(&e, addr)
+    }
 
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    pub fn is_delegating(e: Env, addr: Address) -> bool {
+        bump_instance(&e);
+        is_delegating(&e, addr)
+    }
+}
 
-        let allowance = get_allowance(&e, addr);
-        allowance
+#[contractimpl]
+impl TokenInterface for BallotToken {
+
+    fn allowance(e: Env, from: Address, spender: Address) -> i128 {
+        bump_instance(&e);
+        get_allowance(&e, from, spender).amount
     }
 
-    pub fn blocking(e: &Env, addr: Address) -> bool {
+    fn approve(e: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        check_amount(&e, amount);
+        if is_paused(&e) {
+            panic_with_error!(&e, Error::Paused);
+        }
 
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        bump_instance(&e);
+        set_allowance(&e, from.clone(), spender.clone(), amount, expiration_ledger);
+        e.events().publish((symbol_short!("approve"), from, spender), (amount, expiration_ledger));
+    }
 
-        let blocking = get_blocking(e, addr);
-        blocking
+    fn balance(e: Env, id: Address) -> i128 {
+        bump_instance(&e);
+        get_balance(&e, id)
     }
 
-    pub fn burn(e: Env, addr: Address) {
-        let admin: Address = e.storage().instance().get(&TOKEN_ADMIN).unwrap();
-        admin.require_auth();
+    fn transfer(e: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        check_amount(&e, amount);
+        check_movable(&e, &from);
 
-        e.storage()
-            .instance()
-            .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+        bump_instance(&e);
+        spend_balance(&e, from.clone(), amount);
+        receive_balance(&e, to.clone(), amount);
+        e.events().publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn transfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        check_amount(&e, amount);
+        check_movable(&e, &from);
+
+        bump_instance(&e);
+        spend_allowance(&e, from.clone(), spender, amount);
+        spend_balance(&e, from.clone(), amount);
+        receive_balance(&e, to.clone(), amount);
+        e.events().publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    fn burn(e: Env, from: Address, amount: i128) {
+        from.require_auth();
+        check_amount(&e, amount);
+        check_movable(&e, &from);
 
-        let from_key = DataKey::Balance(addr);
-        e.storage().persistent().remove(&from_key);
+        bump_instance(&e);
+        spend_balance(&e, from.clone(), amount);
+        e.events().publish((symbol_short!("burn"), from), amount);
     }
 
+    fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        check_amount(&e, amount);
+        check_movable(&e, &from);
+
+        bump_instance(&e);
+        spend_allowance(&e, from.clone(), spender, amount);
+        spend_balance(&e, from.clone(), amount);
+        e.events().publish((symbol_short!("burn"), from), amount);
+    }
+
+    fn decimals(_e: Env) -> u32 {
+        DECIMALS
+    }
+
+    fn name(e: Env) -> String {
+        String::from_str(&e, NAME)
+    }
+
+    fn symbol(e: Env) -> String {
+        String::from_str(&e, SYMBOL)
+    }
 }
 
 mod test;
@@ -1,7 +1,7 @@
 #![cfg(test)]
 
-use super::{BallotToken, BallotTokenClient};
-use soroban_sdk::{Env, Address, testutils::{Address as _, Ledger}};
+use super::{BallotToken, BallotTokenClient, Error, SCHEMA_VERSION};
+use soroban_sdk::{Env, Address, BytesN, String, testutils::{Address as _, Ledger}};
 
 #[test]
 fn initialize() {
@@ -9,7 +9,20 @@ fn initialize() {
     let client = create_client(&env);
 
     let admin = Address::generate(&env);
-    assert_eq!(client.initialize(&admin), true);
+    assert!(client.initialize(&admin, &deployer(&env), &salt(&env)));
+}
+
+#[test]
+fn initialize_front_running() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let attacker = Address::generate(&env);
+
+    assert_eq!(client.try_mint(&admin), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_initialize(&attacker, &attacker, &salt(&env)), Err(Ok(Error::NotDeployer)));
+    assert_eq!(client.try_initialize(&attacker, &deployer(&env), &BytesN::from_array(&env, &[8; 32])), Err(Ok(Error::NotDeployer)));
+    assert!(client.initialize(&admin, &deployer(&env), &salt(&env)));
 }
 
 #[test]
@@ -19,8 +32,8 @@ fn already_initialized() {
     let client = create_client(&env);
 
     let admin = Address::generate(&env);
-    client.initialize(&admin);
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.initialize(&admin, &deployer(&env), &salt(&env));
 }
 
 #[test]
@@ -31,7 +44,7 @@ fn mint() {
     let admin = Address::generate(&env);
     let to = Address::generate(&env);
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     assert_eq!(client.mint(&to), 1);
     assert_eq!(client.balance(&to), 1);
 }
@@ -45,7 +58,7 @@ fn already_minted() {
     let admin = Address::generate(&env);
     let to = Address::generate(&env);
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&to);
     client.mint(&to);
 }
@@ -58,9 +71,9 @@ fn transfer() {
     let from = Address::generate(&env);
     let to = Address::generate(&env);
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from);
-    client.transfer(&from, &to);
+    client.transfer(&from, &to, &1);
 
     assert_eq!(client.balance(&from), 0);
     assert_eq!(client.balance(&to), 1);
@@ -68,57 +81,84 @@ fn transfer() {
 }
 
 #[test]
-#[should_panic(expected = "HostError: Error(Contract, #3)")]
-fn transfer_from_does_not_hold_token() {
+fn transfer_to_holder() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
     let from = Address::generate(&env);
     let to = Address::generate(&env);
 
-    client.initialize(&admin);
-    client.transfer(&from, &to);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.mint(&from);
+    client.mint(&to);
+    client.transfer(&from, &to, &1);
+
+    assert_eq!(client.balance(&to), 2);
+    assert_eq!(client.holders(), 1);
 }
 
 #[test]
-#[should_panic(expected = "HostError: Error(Contract, #2)")]
-fn transfer_to_already_holds_token() {
+fn approve_and_transfer_from() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
     let from = Address::generate(&env);
+    let spender = Address::generate(&env);
     let to = Address::generate(&env);
+    env.ledger().with_mut(|li| {li.sequence_number = 100;});
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from);
-    client.mint(&to);
-    client.transfer(&from, &to);
+    client.approve(&from, &spender, &1, &200);
+    assert_eq!(client.allowance(&from, &spender), 1);
+
+    client.transfer_from(&spender, &from, &to, &1);
+    assert_eq!(client.allowance(&from, &spender), 0);
+    assert_eq!(client.balance(&to), 1);
+
+    client.approve(&to, &spender, &1, &200);
+    client.burn_from(&spender, &to, &1);
+    assert_eq!(client.balance(&to), 0);
+
+    client.approve(&from, &spender, &1, &150);
+    env.ledger().with_mut(|li| {li.sequence_number = 151;});
+    assert_eq!(client.allowance(&from, &spender), 0);
+}
+
+#[test]
+fn metadata() {
+    let env = Env::default();
+    let client = create_client(&env);
+
+    assert_eq!(client.decimals(), 0);
+    assert_eq!(client.name(), String::from_str(&env, "Ballot Token"));
+    assert_eq!(client.symbol(), String::from_str(&env, "BALLOT"));
 }
 
 #[test]
-fn no_allowance() {
+fn no_delegated_votes() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
     let addr = Address::generate(&env);
 
-    client.initialize(&admin);
-    assert_eq!(client.allowance(&addr), false);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    assert!(!client.has_delegated_votes(&addr));
 }
 
 #[test]
-fn no_blocking() {
+fn not_delegating() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
     let addr = Address::generate(&env);
 
-    client.initialize(&admin);
-    assert_eq!(client.blocking(&addr), false);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    assert!(!client.is_delegating(&addr));
 }
 
 #[test]
-fn approve_and_allowance() {
+fn delegate_vote() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
@@ -127,18 +167,19 @@ fn approve_and_allowance() {
 
     env.ledger().with_mut(|li| {li.sequence_number = 2499;});
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from);
     client.mint(&spender);
 
-    client.approve(&from, &spender, &2500);
-    assert_eq!(client.allowance(&spender), true);
-    assert_eq!(client.blocking(&from), true);
+    client.delegate_vote(&from, &spender, &2500);
+    assert!(client.has_delegated_votes(&spender));
+    assert!(client.is_delegating(&from));
+    assert_eq!(client.allowance(&from, &spender), 0);
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Contract, #5)")]
-fn approve_expiration_ledger_lower_than_current() {
+fn delegate_vote_expiration_ledger_lower_than_current() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
@@ -146,16 +187,16 @@ fn approve_expiration_ledger_lower_than_current() {
     let spender = Address::generate(&env);
 
     env.ledger().with_mut(|li| {li.sequence_number = 2499;});
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from);
     client.mint(&spender);
 
-    client.approve(&from, &spender, &2498);
+    client.delegate_vote(&from, &spender, &2498);
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Contract, #4)")]
-fn approve_from_already_has_allowance() {
+fn delegate_vote_from_already_delegating() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
@@ -163,17 +204,17 @@ fn approve_from_already_has_allowance() {
     let spender = Address::generate(&env);
     env.ledger().with_mut(|li| {li.sequence_number = 2499;});
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from);
     client.mint(&spender);
 
-    client.approve(&from, &spender, &2500);
-    client.approve(&from, &spender, &2500);
+    client.delegate_vote(&from, &spender, &2500);
+    client.delegate_vote(&from, &spender, &2500);
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Contract, #4)")]
-fn approve_spender_already_has_allowance() {
+fn delegate_vote_to_already_delegated() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
@@ -182,18 +223,18 @@ fn approve_spender_already_has_allowance() {
     let spender = Address::generate(&env);
     env.ledger().with_mut(|li| {li.sequence_number = 2499;});
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&from1);
     client.mint(&from2);
     client.mint(&spender);
 
-    client.approve(&from1, &spender, &2500);
-    client.approve(&from2, &spender, &2500);
+    client.delegate_vote(&from1, &spender, &2500);
+    client.delegate_vote(&from2, &spender, &2500);
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Contract, #3)")]
-fn approve_from_does_not_hold_token() {
+fn delegate_vote_from_does_not_hold_token() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
@@ -201,31 +242,168 @@ fn approve_from_does_not_hold_token() {
     let spender = Address::generate(&env);
     env.ledger().with_mut(|li| {li.sequence_number = 2499;});
 
-    client.initialize(&admin);
+    client.initialize(&admin, &deployer(&env), &salt(&env));
     client.mint(&spender);
 
-    client.approve(&from, &spender, &2500);
+    client.delegate_vote(&from, &spender, &2500);
 }
 
 #[test]
-fn burn() {
+fn burn_and_clawback() {
     let env = Env::default();
     let client = create_client(&env);
     let admin = Address::generate(&env);
-    let addr = Address::generate(&env);
+    let addr1 = Address::generate(&env);
+    let addr2 = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.mint(&addr1);
+    client.mint(&addr2);
+    assert_eq!(client.balance(&addr1), 1);
+
+    client.burn(&addr1, &1);
+    assert_eq!(client.balance(&addr1), 0);
+    client.clawback(&addr2, &1);
+    assert_eq!(client.balance(&addr2), 0);
+}
+
+#[test]
+fn holders() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let addr1 = Address::generate(&env);
+    let addr2 = Address::generate(&env);
+    let addr3 = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    assert_eq!(client.holders(), 0);
 
-    client.initialize(&admin);
-    client.mint(&addr);
-    assert_eq!(client.balance(&addr), 1);
+    client.mint(&addr1);
+    client.mint(&addr2);
+    assert_eq!(client.holders(), 2);
 
-    client.burn(&addr);
-    assert_eq!(client.balance(&addr), 0);
+    client.transfer(&addr2, &addr3, &1);
+    assert_eq!(client.holders(), 2);
+
+    client.clawback(&addr1, &1);
+    assert_eq!(client.holders(), 1);
 }
 
-fn create_client(env: &Env) -> BallotTokenClient{
-    env.mock_all_auths();
-    let contract_id = env.register_contract(None, BallotToken);
-    let client = BallotTokenClient::new(&env, &contract_id);
+#[test]
+fn version() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.mint(&holder);
+    assert_eq!(client.version(), SCHEMA_VERSION);
+
+    // The first upgradeable layout has nothing to move, migrating only records the version
+    assert_eq!(client.migrate(), SCHEMA_VERSION);
+    assert_eq!(client.version(), SCHEMA_VERSION);
+    assert_eq!(client.balance(&holder), 1);
+    assert_eq!(client.holders(), 1);
+}
+
+#[test]
+fn pause() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let from = Address::generate(&env);
+    let to = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.set_guardian(&guardian);
+    client.mint(&from);
+
+    assert!(client.pause(&guardian));
+    assert!(client.paused());
+    assert_eq!(client.try_delegate_vote(&from, &to, &2500), Err(Ok(Error::Paused)));
+    assert_eq!(client.try_unpause(&from), Err(Ok(Error::NotAuthorized)));
+
+    assert!(client.unpause(&admin));
+    client.transfer(&from, &to, &1);
+    assert_eq!(client.balance(&to), 1);
+}
+
+#[test]
+fn lock() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let locker = Address::generate(&env);
+    let from = Address::generate(&env);
+    let to = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    client.mint(&from);
 
-    client
+    assert_eq!(client.try_lock(&locker, &from, &500), Err(Ok(Error::NotAuthorized)));
+    client.set_locker(&locker, &true);
+    assert!(!client.lock(&locker, &to, &500));
+    assert!(client.lock(&locker, &from, &500));
+    assert!(client.lock(&locker, &from, &300));
+    assert_eq!(client.locked_until(&from), 500);
+
+    env.ledger().with_mut(|l| {l.sequence_number = 501;});
+    assert_eq!(client.locked_until(&from), 0);
+    client.transfer(&from, &to, &1);
+    assert_eq!(client.balance(&to), 1);
+
+    // Locks may outlast the longest TTL an entry can get
+    assert!(client.lock(&locker, &to, &u32::MAX));
+    assert_eq!(client.locked_until(&to), u32::MAX);
+}
+
+#[test]
+fn checkpoints() {
+    let env = Env::default();
+    let client = create_client(&env);
+    let admin = Address::generate(&env);
+    let from = Address::generate(&env);
+    let to = Address::generate(&env);
+
+    client.initialize(&admin, &deployer(&env), &salt(&env));
+    env.ledger().with_mut(|l| {l.sequence_number = 10;});
+    client.mint(&from);
+    env.ledger().with_mut(|l| {l.sequence_number = 20;});
+    client.mint(&to);
+    client.clawback(&to, &1);
+    env.ledger().with_mut(|l| {l.sequence_number = 30;});
+    client.transfer(&from, &to, &1);
+
+    assert_eq!(client.balance_at(&from, &9), 0);
+    assert_eq!(client.balance_at(&from, &10), 1);
+    assert_eq!(client.balance_at(&from, &29), 1);
+    assert_eq!(client.balance_at(&from, &30), 0);
+    assert_eq!(client.balance_at(&to, &20), 0);
+    assert_eq!(client.balance_at(&to, &30), 1);
+
+    assert_eq!(client.holders_at(&5), 0);
+    assert_eq!(client.holders_at(&15), 1);
+    assert_eq!(client.holders_at(&20), 1);
+    assert_eq!(client.holders_at(&30), 1);
+    assert_eq!(client.balance_at(&Address::generate(&env), &30), 0);
+}
+
+fn deployer(env: &Env) -> Address {
+    Address::from_string(&String::from_str(env, "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"))
+}
+
+fn salt(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[7; 32])
+}
+
+// Registers the token where deployer would have deployed it with salt
+fn create_client(env: &Env) -> BallotTokenClient<'_> {
+    env.mock_all_auths();
+    let address = env.deployer().with_address(deployer(env), salt(env)).deployed_address();
+    let contract_id = env.register_contract(Some(&address), BallotToken);
+    BallotTokenClient::new(env, &contract_id)
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+#![no_std]
+
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Symbol};
+
+pub const ADMIN: Symbol = symbol_short!("admin");
+
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const LIFETIME_THRESHOLD: u32 = BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    NegativeWeight = 2,
+    NotInitialized = 3,
+    NotDeployer = 4
+}
+
+#[contracttype]
+pub enum DataKey {
+    Weight(Address)
+}
+
+fn get_admin(env: &Env) -> Result<Address, Error> {
+    env.storage().instance().get(&ADMIN).ok_or(Error::NotInitialized)
+}
+
+fn get_weight(env: &Env, addr: Address) -> i128 {
+    let key = DataKey::Weight(addr);
+    if let Some(w) = env.storage().persistent().get::<_, i128>(&key) {
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        return w;
+    }
+
+    0
+}
+
+// Eligibility provider backed by a list of addresses the admin maintains, each one with its own weight
+#[contract]
+pub struct AllowList;
+
+#[contractimpl]
+impl AllowList {
+
+    pub fn initialize(env: Env, admin: Address, deployer: Address, salt: BytesN<32>) -> Result<bool, Error> {
+        if env.storage().instance().has(&ADMIN) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        if env.deployer().with_address(deployer.clone(), salt).deployed_address() != env.current_contract_address() {
+            return Err(Error::NotDeployer);
+        }
+
+        deployer.require_auth();
+        env.storage().instance().set(&ADMIN, &admin);
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(true)
+    }
+
+    // A zero weight takes the address out of the list
+    pub fn set_weight(env: Env, addr: Address, weight: i128) -> Result<bool, Error> {
+        get_admin(&env)?.require_auth();
+        if weight < 0 {
+            return Err(Error::NegativeWeight);
+        }
+
+        let key = DataKey::Weight(addr);
+        if weight == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &weight);
+            env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        }
+
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(true)
+    }
+
+    pub fn is_eligible(env: Env, addr: Address) -> bool {
+        get_weight(&env, addr) > 0
+    }
+
+    pub fn weight(env: Env, addr: Address) -> i128 {
+        get_weight(&env, addr)
+    }
+}
+
+mod test;
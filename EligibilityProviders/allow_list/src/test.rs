@@ -0,0 +1,31 @@
+#![cfg(test)]
+
+use super::{AllowList, AllowListClient, Error};
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env};
+
+#[test]
+fn allow_list_test() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let deployer = Address::generate(&env);
+    let salt = BytesN::from_array(&env, &[1; 32]);
+    let address = env.deployer().with_address(deployer.clone(), salt.clone()).deployed_address();
+    let client = AllowListClient::new(&env, &env.register_contract(&address, AllowList));
+    let admin = Address::generate(&env);
+    let member = Address::generate(&env);
+
+    assert_eq!(client.try_set_weight(&member, &4), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_initialize(&admin, &Address::generate(&env), &salt), Err(Ok(Error::NotDeployer)));
+    client.initialize(&admin, &deployer, &salt);
+    assert_eq!(client.try_initialize(&admin, &deployer, &salt), Err(Ok(Error::AlreadyInitialized)));
+    assert!(!client.is_eligible(&member));
+
+    client.set_weight(&member, &4);
+    assert!(client.is_eligible(&member));
+    assert_eq!(client.weight(&member), 4);
+    assert_eq!(client.try_set_weight(&member, &-1), Err(Ok(Error::NegativeWeight)));
+
+    client.set_weight(&member, &0);
+    assert!(!client.is_eligible(&member));
+    assert_eq!(client.weight(&member), 0);
+}
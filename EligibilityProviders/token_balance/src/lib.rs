@@ -0,0 +1,89 @@
+#![no_std]
+
+use soroban_sdk::{contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Symbol};
+
+pub const CONFIG: Symbol = symbol_short!("config");
+
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const LIFETIME_THRESHOLD: u32 = BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    InvalidMinimum = 2,
+    NotDeployer = 3,
+    NotInitialized = 4,
+    InvalidSnapshot = 5
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Config {
+    pub token: Address,
+    pub min_balance: i128,
+    pub snapshot: u32
+}
+
+// Tokens recording balance checkpoints, such as BallotToken
+#[contractclient(name = "CheckpointTokenClient")]
+pub trait CheckpointToken {
+    fn balance_at(env: Env, addr: Address, ledger: u32) -> i128;
+}
+
+fn get_config(env: &Env) -> Result<Config, Error> {
+    let cfg = env.storage().instance().get(&CONFIG).ok_or(Error::NotInitialized)?;
+    env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    Ok(cfg)
+}
+
+// Eligibility provider for holders of a token recording checkpoints, voting with their balance at a past ledger as weight.
+// Live balances would let a holder vote, move the tokens and vote again from another address
+#[contract]
+pub struct TokenBalance;
+
+#[contractimpl]
+impl TokenBalance {
+
+    pub fn initialize(env: Env, token: Address, min_balance: i128, snapshot: u32, deployer: Address, salt: BytesN<32>) -> Result<bool, Error> {
+        if env.storage().instance().has(&CONFIG) {
+            return Err(Error::AlreadyInitialized);
+        }
+
+        if env.deployer().with_address(deployer.clone(), salt).deployed_address() != env.current_contract_address() {
+            return Err(Error::NotDeployer);
+        }
+
+        deployer.require_auth();
+        if min_balance < 1 {
+            return Err(Error::InvalidMinimum);
+        }
+
+        // Balances are final only once the snapshot ledger has closed
+        if snapshot >= env.ledger().sequence() {
+            return Err(Error::InvalidSnapshot);
+        }
+
+        env.storage().instance().set(&CONFIG, &Config { token, min_balance, snapshot });
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(true)
+    }
+
+    pub fn is_eligible(env: Env, addr: Address) -> Result<bool, Error> {
+        Ok(Self::weight(env, addr)? > 0)
+    }
+
+    pub fn weight(env: Env, addr: Address) -> Result<i128, Error> {
+        let cfg = get_config(&env)?;
+        let balance = CheckpointTokenClient::new(&env, &cfg.token).balance_at(&addr, &cfg.snapshot);
+        if balance < cfg.min_balance {
+            return Ok(0);
+        }
+
+        Ok(balance)
+    }
+}
+
+mod test;
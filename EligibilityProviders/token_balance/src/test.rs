@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use super::{Error, TokenBalance, TokenBalanceClient};
+use soroban_sdk::{contract, contractimpl, testutils::{Address as _, Ledger}, Address, BytesN, Env};
+
+// Token whose balances at any ledger are set by the test
+#[contract]
+pub struct CheckpointToken;
+
+#[contractimpl]
+impl CheckpointToken {
+    pub fn set_balance_at(env: Env, addr: Address, ledger: u32, balance: i128) {
+        env.storage().instance().set(&(addr, ledger), &balance);
+    }
+
+    pub fn balance_at(env: Env, addr: Address, ledger: u32) -> i128 {
+        env.storage().instance().get(&(addr, ledger)).unwrap_or(0)
+    }
+}
+
+fn create_client(env: &Env, salt: u8) -> (TokenBalanceClient<'_>, Address, BytesN<32>) {
+    env.mock_all_auths();
+    let deployer = Address::generate(env);
+    let salt = BytesN::from_array(env, &[salt; 32]);
+    let address = env.deployer().with_address(deployer.clone(), salt.clone()).deployed_address();
+    let client = TokenBalanceClient::new(env, &env.register_contract(&address, TokenBalance));
+    (client, deployer, salt)
+}
+
+#[test]
+fn token_balance_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let (client, deployer, salt) = create_client(&env, 1);
+    let token = CheckpointTokenClient::new(&env, &env.register_contract(None, CheckpointToken));
+    let holder = Address::generate(&env);
+    let small = Address::generate(&env);
+    token.set_balance_at(&holder, &50, &250);
+    token.set_balance_at(&small, &50, &10);
+
+    assert_eq!(client.try_weight(&holder), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_initialize(&token.address, &100, &50, &Address::generate(&env), &salt), Err(Ok(Error::NotDeployer)));
+    assert_eq!(client.try_initialize(&token.address, &0, &50, &deployer, &salt), Err(Ok(Error::InvalidMinimum)));
+    assert_eq!(client.try_initialize(&token.address, &100, &100, &deployer, &salt), Err(Ok(Error::InvalidSnapshot)));
+    client.initialize(&token.address, &100, &50, &deployer, &salt);
+    assert_eq!(client.try_initialize(&token.address, &100, &50, &deployer, &salt), Err(Ok(Error::AlreadyInitialized)));
+
+    assert!(client.is_eligible(&holder));
+    assert_eq!(client.weight(&holder), 250);
+    assert!(!client.is_eligible(&small));
+    assert_eq!(client.weight(&small), 0);
+    assert!(!client.is_eligible(&Address::generate(&env)));
+}
+
+#[test]
+fn token_balance_snapshot_test() {
+    let env = Env::default();
+    env.ledger().with_mut(|l| {l.sequence_number = 100;});
+    let (client, deployer, salt) = create_client(&env, 2);
+    let token = CheckpointTokenClient::new(&env, &env.register_contract(None, CheckpointToken));
+    let holder = Address::generate(&env);
+    let receiver = Address::generate(&env);
+
+    // The holder moved its balance to receiver after the snapshot, only the snapshot balance counts
+    token.set_balance_at(&holder, &50, &300);
+    token.set_balance_at(&receiver, &60, &300);
+
+    client.initialize(&token.address, &100, &50, &deployer, &salt);
+    assert_eq!(client.weight(&holder), 300);
+    assert!(!client.is_eligible(&receiver));
+}